@@ -0,0 +1,177 @@
+//! Checks levels and campaigns for problems without opening a window
+//!
+//! Run from the root of the game with paths to `.lvl`, `.toml` and `.cmp` files or directories of them.
+//! Without any paths `levels/` and `campaigns/` are checked.
+#![warn(clippy::all)]
+
+use std::env::args;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use topskud::{
+    util::Point2,
+    game::world::{Grid, Level, TEXT_EXTENSION},
+    obj::decoration::Prop,
+};
+
+#[derive(Debug, Default)]
+struct Report {
+    problems: usize,
+    /// Levels that have been checked already, as campaigns can refer to the ones in the directories given
+    checked: HashSet<PathBuf>,
+}
+
+impl Report {
+    fn problem<D: std::fmt::Display>(&mut self, path: &Path, msg: D) {
+        self.problems += 1;
+        println!("{}: {}", path.display(), msg);
+    }
+}
+
+/// Shows a point rounded to whole pixels, which nalgebra's `Display` doesn't do
+struct Pos(Point2);
+
+impl std::fmt::Display for Pos {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({:.0}, {:.0})", self.0.x, self.0.y)
+    }
+}
+
+fn is_level(path: &Path) -> bool {
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("lvl") => true,
+        Some(ext) => ext == TEXT_EXTENSION,
+        None => false,
+    }
+}
+
+fn is_campaign(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("cmp")
+}
+
+fn lint_level(report: &mut Report, path: &Path) {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+    if !report.checked.insert(canonical) {
+        return
+    }
+    let level = match Level::load(path) {
+        Ok(level) => level,
+        Err(e) => return report.problem(path, format_args!("couldn't load: {}", e)),
+    };
+    let Level{palette, grid, start_point, exit, intels, enemies, pickups, decorations, weapons} = &level;
+
+    let in_solid = |p: Point2| grid.is_solid_tuple(palette, Grid::snap(p));
+
+    for enemy in enemies {
+        if enemy.pl.obj.is_on_solid(palette, grid) {
+            report.problem(path, format_args!("enemy at {} is inside a solid tile", Pos(enemy.pl.obj.pos)));
+        }
+        if !Path::new("resources").join(format!("{}.png", enemy.archetype.sprite)).exists() {
+            report.problem(path, format_args!("{} at {} has missing sprite {}", enemy.archetype.id, Pos(enemy.pl.obj.pos), enemy.archetype.sprite));
+        }
+        for wp in &enemy.patrol.waypoints {
+            if in_solid(wp.pos) {
+                report.problem(path, format_args!("patrol waypoint at {} is inside a solid tile", Pos(wp.pos)));
+            } else if grid.find_path(palette, enemy.pl.obj.pos, wp.pos).is_none() {
+                report.problem(path, format_args!("patrol waypoint at {} can't be reached by the enemy at {}", Pos(wp.pos), Pos(enemy.pl.obj.pos)));
+            }
+        }
+    }
+    for &(p, pickup) in pickups {
+        if in_solid(p) {
+            report.problem(path, format_args!("{} at {} is inside a solid tile", pickup.id, Pos(p)));
+        }
+        if !Path::new("resources").join(format!("{}.png", pickup.spr)).exists() {
+            report.problem(path, format_args!("{} at {} has missing sprite {}", pickup.id, Pos(p), pickup.spr));
+        }
+    }
+    for wep in weapons {
+        if in_solid(wep.pos) {
+            report.problem(path, format_args!("{} at {} is inside a solid tile", wep.weapon.name, Pos(wep.pos)));
+        }
+    }
+    for dec in decorations {
+        if !Path::new("resources").join(format!("{}.png", dec.spr)).exists() {
+            report.problem(path, format_args!("decoration at {} has missing sprite {}", Pos(dec.obj.pos), dec.spr));
+        }
+        if let Some(Prop{destroyed: Some(spr), ..}) = dec.prop() {
+            if !Path::new("resources").join(format!("{}.png", spr)).exists() {
+                report.problem(path, format_args!("{} at {} has missing destroyed sprite {}", dec.spr, Pos(dec.obj.pos), spr));
+            }
+        }
+    }
+
+    let start = if let Some(start) = *start_point {
+        start
+    } else {
+        return report.problem(path, "no start point");
+    };
+    if in_solid(start) {
+        return report.problem(path, format_args!("start point {} is inside a solid tile", Pos(start)));
+    }
+    let walkable = grid.walkable_from(palette, Grid::snap(start));
+
+    if let Some(exit) = *exit {
+        if !walkable.contains(Grid::snap(exit)) {
+            report.problem(path, format_args!("exit at {} can't be reached from the start", Pos(exit)));
+        }
+    }
+    for &intel in intels {
+        if !walkable.contains(Grid::snap(intel)) {
+            report.problem(path, format_args!("intel at {} can't be reached from the start", Pos(intel)));
+        }
+    }
+}
+
+fn lint_campaign(report: &mut Report, path: &Path) {
+    let file = match File::open(path) {
+        Ok(f) => BufReader::new(f),
+        Err(e) => return report.problem(path, format_args!("couldn't open: {}", e)),
+    };
+    for line in file.lines() {
+        match line {
+            Ok(line) => lint_level(report, Path::new(line.trim())),
+            Err(e) => return report.problem(path, format_args!("couldn't read: {}", e)),
+        }
+    }
+}
+
+fn lint(report: &mut Report, path: &Path) {
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = match fs::read_dir(path) {
+            Ok(dir) => dir.filter_map(Result::ok).map(|d| d.path()).collect(),
+            Err(e) => return report.problem(path, format_args!("couldn't read directory: {}", e)),
+        };
+        entries.sort();
+        for entry in entries {
+            if is_level(&entry) || is_campaign(&entry) {
+                lint(report, &entry);
+            }
+        }
+    } else if is_campaign(path) {
+        lint_campaign(report, path);
+    } else {
+        lint_level(report, path);
+    }
+}
+
+fn main() {
+    let mut paths: Vec<PathBuf> = args().skip(1).map(PathBuf::from).collect();
+    if paths.is_empty() {
+        paths.push("levels".into());
+        paths.push("campaigns".into());
+    }
+
+    let mut report = Report::default();
+    for path in &paths {
+        lint(&mut report, path);
+    }
+
+    if report.problems > 0 {
+        eprintln!("{} problem(s) found", report.problems);
+        exit(1);
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// How hard the game is, scaling the damage the player takes, the enemies and the resources lying around
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl Default for Difficulty {
+    #[inline]
+    fn default() -> Self {
+        Difficulty::Normal
+    }
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Insane => "insane",
+        }
+    }
+    /// The next harder difficulty, wrapping around to the easiest
+    pub fn next(self) -> Self {
+        let i = Difficulty::ALL.iter().position(|&d| d == self).unwrap();
+        Difficulty::ALL[(i + 1) % Difficulty::ALL.len()]
+    }
+    /// Factor on the damage the player takes
+    pub fn damage_taken(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 1.5,
+            Difficulty::Insane => 2.,
+        }
+    }
+    /// Factor on how long enemies take to react to seeing the player
+    pub fn reaction_time(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.6,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 0.75,
+            Difficulty::Insane => 0.5,
+        }
+    }
+    /// Factor on how far enemies can see
+    pub fn vision_range(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.8,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 1.15,
+            Difficulty::Insane => 1.3,
+        }
+    }
+    /// How well enemies aim, their aim errors are divided by this
+    pub fn accuracy(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.6,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 1.4,
+            Difficulty::Insane => 2.,
+        }
+    }
+    /// Factor on the spare ammo weapons start with
+    pub fn ammo(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 0.75,
+            Difficulty::Insane => 0.5,
+        }
+    }
+    /// Factor on how much pickups give the player
+    pub fn pickups(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.,
+            Difficulty::Hard => 0.75,
+            Difficulty::Insane => 0.5,
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Difficulty::ALL.iter()
+            .cloned()
+            .find(|d| d.name() == s)
+            .ok_or_else(|| format!("unknown difficulty {:?}, expected one of easy, normal, hard or insane", s))
+    }
+}
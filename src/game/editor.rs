@@ -3,10 +3,11 @@ use crate::{
         ver,
         hor,
         TRANS,
+        BLUE,
         Vector2, Point2},
     io::tex::PosText,
     ext::BoolExt,
-    obj::{Object, enemy::Enemy, decoration::Decoration, pickup::PICKUPS, weapon::WEAPONS}
+    obj::{Object, enemy::{Enemy, Archetype, PatrolMode, Waypoint, TACTICS, ARCHETYPES}, decoration::Decoration, pickup::{PickupType, PICKUPS}, weapon::WEAPONS}
 };
 use ggez::{
     Context, GameResult,
@@ -31,14 +32,16 @@ use std::fs::File;
 enum Tool {
     Inserter(Insertion),
     Selector(Selection),
+    /// Adding waypoints to the patrol of the enemy with the index
+    Patrol(usize),
 }
 
 #[derive(Debug, Clone, Copy)]
 enum Insertion {
     Material(u8),
     Intel,
-    Enemy{rot: f32},
-    Pickup(u8),
+    Enemy{archetype: &'static Archetype, rot: f32},
+    Pickup(&'static PickupType),
     Weapon(&'static str),
     Decoration{spr: &'static str, rot: f32},
     Exit,
@@ -49,9 +52,9 @@ impl Insertion {
         match *self {
             Material(_) => panic!("Get it yourself. I don't have the palette"),
             Intel => "common/intel",
-            Enemy{..} => "common/enemy",
+            Enemy{archetype, ..} => &*archetype.sprite,
             Exit => "common/goal",
-            Pickup(i) => PICKUPS[i as usize].spr,
+            Pickup(pickup) => &*pickup.spr,
             Weapon(wep) => &*WEAPONS[wep].entity_sprite, 
             Decoration{spr, ..} => spr,
         }
@@ -63,8 +66,8 @@ impl ::std::cmp::PartialEq for Insertion {
         match (self, rhs) {
             (Material(m), Material(n)) if m == n => true,
             (Intel, Intel) => true,
-            (Enemy{..}, Enemy{..}) => true,
-            (Pickup(i), Pickup(j)) if i == j => true,
+            (Enemy{archetype, ..}, Enemy{archetype: archetype2, ..}) if archetype.id == archetype2.id => true,
+            (Pickup(pickup), Pickup(pickup2)) if pickup.id == pickup2.id => true,
             (Weapon(i), Weapon(j)) if i == j => true,
             (Decoration{spr, ..}, Decoration{spr: spr2, ..}) if spr == spr2 => true,
             (Exit, Exit) => true,
@@ -92,6 +95,8 @@ struct EditorFile {
 #[derive(Debug, Serialize, Deserialize)]
 struct EditorPalettes {
     materials: Vec<String>,
+    enemies: Vec<String>,
+    pickups: Vec<String>,
     weapons: Vec<String>,
     decorations: Vec<String>,
 }
@@ -163,22 +168,25 @@ impl Editor {
     pub fn new(s: &State, level: Option<Level>) -> GameResult<Box<dyn GameState>> {
         let mat_text = s.assets.text(Point2::new(2., 18.0)).and_text("Materials:");
         let mut entities = vec![
-            Insertion::Enemy{rot: 0.},
             Insertion::Exit,
             Insertion::Intel,
-            Insertion::Pickup(0),
-            Insertion::Pickup(1),
-            Insertion::Pickup(2),
-            Insertion::Pickup(3),
         ];
 
-        let EditorFile{palettes: EditorPalettes{materials, weapons, decorations}} = {
+        let EditorFile{palettes: EditorPalettes{materials, enemies, pickups, weapons, decorations}} = {
             let mut file = File::open("resources/editor.toml").unwrap();
             let mut s = String::new();
             file.read_to_string(&mut s).unwrap();
             
             toml::from_str(&s).unwrap()
         };
+        for (i, id) in enemies.iter().enumerate() {
+            let archetype = ARCHETYPES.get(id).ok_or_else(|| GameError::ResourceLoadError(format!("Unknown archetype {:?} in editor.toml", id)))?;
+            entities.insert(i, Insertion::Enemy{archetype, rot: 0.});
+        }
+        for id in &pickups {
+            let pickup = PICKUPS.get(id).ok_or_else(|| GameError::ResourceLoadError(format!("Unknown pickup {:?} in editor.toml", id)))?;
+            entities.push(Insertion::Pickup(pickup));
+        }
         entities.extend(weapons.into_iter().map(|wep| Insertion::Weapon(&*Box::leak(wep.into_boxed_str()))));
         entities.extend(decorations.into_iter().map(|dec| Insertion::Decoration{rot: 0., spr: &*Box::leak(dec.into_boxed_str())}));
 
@@ -230,6 +238,28 @@ impl Editor {
 const START_X: f32 = 103.;
 const YELLOW: Color = Color{r: 1., g: 1., b: 0., a: 1.};
 
+/// Draws the route of an enemy's patrol, bigger dots are waypoints with a wait and hollow ones are where it looks around
+fn draw_patrol(ctx: &mut Context, enemy: &Enemy, color: Color) -> GameResult<()> {
+    let patrol = &enemy.patrol;
+
+    let mut points = Vec::with_capacity(patrol.waypoints.len() + 2);
+    points.push(enemy.pl.obj.pos);
+    points.extend(patrol.waypoints.iter().map(|wp| wp.pos));
+    if patrol.mode == PatrolMode::Loop && patrol.waypoints.len() > 1 {
+        points.push(patrol.waypoints[0].pos);
+    }
+    let mesh = Mesh::new_polyline(ctx, DrawMode::stroke(1.5), &points, color)?;
+    graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+    for wp in &patrol.waypoints {
+        let radius = if wp.wait > 0. { 6. } else { 3. };
+        let mode = if wp.look_around { DrawMode::stroke(1.5) } else { DrawMode::fill() };
+        let mesh = Mesh::new_circle(ctx, mode, wp.pos, radius, 0.5, color)?;
+        graphics::draw(ctx, &mesh, DrawParam::default())?;
+    }
+    Ok(())
+}
+
 impl GameState for Editor {
     fn update(&mut self, _s: &mut State, ctx: &mut Context) -> GameResult<()> {
         let speed = if keyboard::is_mod_active(ctx, KeyMods::SHIFT) { 315. } else { 175. };
@@ -237,7 +267,7 @@ impl GameState for Editor {
         self.pos += v * DELTA;
 
         match self.current {
-            Tool::Inserter(Insertion::Enemy{ref mut rot}) => *rot += self.rotation_speed * DELTA,
+            Tool::Inserter(Insertion::Enemy{ref mut rot, ..}) => *rot += self.rotation_speed * DELTA,
             Tool::Inserter(Insertion::Decoration{ref mut rot, ..}) => *rot += self.rotation_speed * DELTA,
             _ => (),
         }
@@ -312,10 +342,14 @@ impl GameState for Editor {
                 if enemies.contains(&i) {
                     let mesh = Mesh::new_circle(ctx, DrawMode::fill(), enemy.pl.obj.pos, 17., 0.5, YELLOW)?;
                     graphics::draw(ctx, &mesh, DrawParam::default())?;
+                    s.assets.text(enemy.pl.obj.pos + Vector2::new(-16., 18.)).and_text(&*enemy.archetype.id).and_text(" (").and_text(&*enemy.tactics.id).and_text(")").draw_text(ctx)?;
                 }
             }
             if self.draw_visibility_cones {
-                enemy.draw_visibility_cone(ctx, 512.)?;
+                enemy.draw_visibility_cone(ctx)?;
+            }
+            if !enemy.patrol.is_empty() {
+                draw_patrol(ctx, enemy, if self.current == Tool::Patrol(i) { YELLOW } else { BLUE })?;
             }
             enemy.draw(ctx, &s.assets, WHITE)?;
         }
@@ -337,7 +371,7 @@ impl GameState for Editor {
                     graphics::draw(ctx, &mesh, DrawParam::default())?;
                 }
             }
-            PICKUPS[pickup.1 as usize].draw(pickup.0, ctx, &s.assets)?;
+            pickup.1.draw(pickup.0, ctx, &s.assets)?;
         }
         for (i, weapon) in self.level.weapons.iter().enumerate() {
             if let Tool::Selector(Selection{ref weapons, ..}) = self.current {
@@ -388,7 +422,7 @@ impl GameState for Editor {
                     color: TRANS,
                     .. Default::default()
                 };
-                let img = s.assets.get_img(ctx, PICKUPS[pickup.1 as usize].spr);
+                let img = s.assets.get_img(ctx, &pickup.1.spr);
                 graphics::draw(ctx, &*img, drawparams)?;
             }
             for &i in &selection.weapons {
@@ -420,9 +454,9 @@ impl GameState for Editor {
     fn draw_hud(&mut self, s: &State, ctx: &mut Context) -> GameResult<()> {
         let dest = (self.mousepos(s) + s.offset).into();
         match self.current {
-            Tool::Selector(_) => (),
+            Tool::Selector(_) | Tool::Patrol(_) => (),
             Tool::Inserter(Insertion::Material(_)) => (),
-            Tool::Inserter(Insertion::Pickup(index)) => {
+            Tool::Inserter(Insertion::Pickup(pickup)) => {
                 let drawparams = graphics::DrawParam {
                     dest,
                     rotation: 0.,
@@ -430,7 +464,7 @@ impl GameState for Editor {
                     color: TRANS,
                     .. Default::default()
                 };
-                let img = s.assets.get_img(ctx, PICKUPS[index as usize].spr);
+                let img = s.assets.get_img(ctx, &pickup.spr);
                 graphics::draw(ctx, &*img, drawparams)?;
             }
             Tool::Inserter(Insertion::Weapon(id)) => {
@@ -444,7 +478,7 @@ impl GameState for Editor {
                 let img = s.assets.get_img(ctx, &WEAPONS[id].entity_sprite);
                 graphics::draw(ctx, &*img, drawparams)?;
             }
-            Tool::Inserter(Insertion::Enemy{rot}) => {
+            Tool::Inserter(Insertion::Enemy{archetype, rot}) => {
                 let drawparams = graphics::DrawParam {
                     dest,
                     rotation: rot,
@@ -452,8 +486,9 @@ impl GameState for Editor {
                     color: TRANS,
                     .. Default::default()
                 };
-                let img = s.assets.get_img(ctx, "common/enemy");
+                let img = s.assets.get_img(ctx, &archetype.sprite);
                 graphics::draw(ctx, &*img, drawparams)?;
+                s.assets.text(self.mousepos(s) + s.offset + Vector2::new(-16., 18.)).and_text(&*archetype.id).draw_text(ctx)?;
             }
             Tool::Inserter(Insertion::Decoration{spr, rot}) => {
                 let drawparams = graphics::DrawParam {
@@ -515,14 +550,54 @@ impl GameState for Editor {
         use self::KeyCode::*;
         match keycode {
             Z => self.level.save(&self.save).unwrap(),
-            X => self.level = Level::load(&self.save).unwrap(),
+            X => {
+                self.level = Level::load(&self.save).unwrap();
+                self.current = Tool::Selector(Selection::default());
+            }
             C => self.draw_visibility_cones.toggle(),
             G => self.snap_on_grid.toggle(),
             P => {
                 s.switch(StateSwitch::Play(self.level.clone()));
             }
             T => self.current = Tool::Selector(Selection::default()),
-            Delete | Back => if let Tool::Selector(ref mut selection) = self.current {
+            R => match self.current {
+                Tool::Selector(Selection{ref enemies, ..}) if enemies.len() == 1 => self.current = Tool::Patrol(enemies[0]),
+                Tool::Patrol(i) => self.current = Tool::Selector(Selection{enemies: vec![i], .. Default::default()}),
+                _ => (),
+            }
+            U => if let Tool::Selector(Selection{ref enemies, ..}) = self.current {
+                let mut ids: Vec<_> = TACTICS.keys().collect();
+                ids.sort();
+                for &i in enemies {
+                    let enemy = &mut self.level.enemies[i];
+                    let cur = ids.iter().position(|id| **id == *enemy.tactics.id).unwrap_or(0);
+                    enemy.tactics = &TACTICS[ids[(cur + 1) % ids.len()]];
+                }
+            }
+            L => if let Tool::Patrol(i) = self.current {
+                let patrol = &mut self.level.enemies[i].patrol;
+                patrol.mode = match patrol.mode {
+                    PatrolMode::Loop => PatrolMode::PingPong,
+                    PatrolMode::PingPong => PatrolMode::Loop,
+                };
+            }
+            H => if let Tool::Patrol(i) = self.current {
+                if let Some(wp) = self.level.enemies[i].patrol.waypoints.last_mut() {
+                    if shift {
+                        wp.wait = 0.;
+                    } else {
+                        wp.wait += 1.;
+                    }
+                }
+            }
+            K => if let Tool::Patrol(i) = self.current {
+                if let Some(wp) = self.level.enemies[i].patrol.waypoints.last_mut() {
+                    wp.look_around.toggle();
+                }
+            }
+            Delete | Back => if let Tool::Patrol(i) = self.current {
+                self.level.enemies[i].patrol.waypoints.pop();
+            } else if let Tool::Selector(ref mut selection) = self.current {
                 #[allow(clippy::unneeded_field_pattern)]
                 let Selection {
                     mut enemies,
@@ -561,7 +636,7 @@ impl GameState for Editor {
                 self.rotation_speed = 0.;
                 if shift {
                     match self.current {
-                        Tool::Inserter(Insertion::Enemy{ref mut rot}) => *rot -= std::f32::consts::FRAC_PI_4,
+                        Tool::Inserter(Insertion::Enemy{ref mut rot, ..}) => *rot -= std::f32::consts::FRAC_PI_4,
                         Tool::Inserter(Insertion::Decoration{ref mut rot, ..}) => *rot -= std::f32::consts::FRAC_PI_4,
                         _ => (),
                     }
@@ -571,7 +646,7 @@ impl GameState for Editor {
                 self.rotation_speed = 0.;
                 if shift {
                     match self.current {
-                        Tool::Inserter(Insertion::Enemy{ref mut rot}) => *rot += std::f32::consts::FRAC_PI_4,
+                        Tool::Inserter(Insertion::Enemy{ref mut rot, ..}) => *rot += std::f32::consts::FRAC_PI_4,
                         Tool::Inserter(Insertion::Decoration{ref mut rot, ..}) => *rot += std::f32::consts::FRAC_PI_4,
                         _ => (),
                     }
@@ -647,6 +722,7 @@ impl GameState for Editor {
             } else {
                 match self.current {
                     Tool::Inserter(Insertion::Material(_)) => (),
+                    Tool::Patrol(i) => self.level.enemies[i].patrol.waypoints.push(Waypoint::new(mousepos)),
                     Tool::Selector(ref mut selection) => {
 
                         if let Some(moved_from) = selection.moving {
@@ -719,16 +795,15 @@ impl GameState for Editor {
                         self.level.exit = Some(self.mousepos(&s));
                         self.current = Tool::Selector(Selection{exit: true, .. Default::default()});
                     }
-                    Tool::Inserter(Insertion::Enemy{rot}) => {
+                    Tool::Inserter(Insertion::Enemy{archetype, rot}) => {
                         s.mplayer.play(ctx, "reload").unwrap();
-                        self.level.enemies.push(Enemy::new(Object::with_rot(mousepos, rot)));
-                        self.level.weapons.push(WEAPONS["glock"].make_drop(mousepos));
+                        self.level.enemies.push(Enemy::from_archetype(Object::with_rot(mousepos, rot), archetype));
                     },
                     Tool::Inserter(Insertion::Decoration{spr, rot}) => {
                         self.level.decorations.push(Decoration::new(Object::with_rot(mousepos, rot), spr));
                     }
-                    Tool::Inserter(Insertion::Pickup(i)) => {
-                        self.level.pickups.push((mousepos, i));
+                    Tool::Inserter(Insertion::Pickup(pickup)) => {
+                        self.level.pickups.push((mousepos, pickup));
                     },
                     Tool::Inserter(Insertion::Weapon(id)) => {
                         self.level.weapons.push(WEAPONS[id].make_drop(mousepos));
@@ -4,7 +4,7 @@ use crate::{
         tex::PosText,
         btn::Button,
     },
-    obj::{health::Health, weapon::WeaponInstance},
+    obj::{health::Health, player::Inventory},
 };
 use ggez::{
     Context, GameResult,
@@ -19,12 +19,13 @@ pub struct Lose {
     you_died: PosText,
     hits_text: PosText,
     misses_text: PosText,
+    headshots_text: PosText,
     enemies_text: PosText,
     restart_btn: Button<()>,
     edit_btn: Option<Button<()>>,
     level: Level,
     health: Health,
-    weapon: Option<WeaponInstance<'static>>
+    inventory: Inventory,
 }
 
 impl Lose {
@@ -34,7 +35,8 @@ impl Lose {
         let you_died = s.assets.text(Point2::new(s.width as f32/ 2., 10.)).and_text(TextFragment::from("You died!").color(RED));
         let hits_text = s.assets.text(Point2::new(4., 20.)).and_text(format!("Hits: {}", stats.hits));
         let misses_text = s.assets.text(Point2::new(4., 36.)).and_text(format!("Misses: {}", stats.misses));
-        let enemies_text = s.assets.text(Point2::new(4., 52.)).and_text(format!("Enemies left: {}", stats.enemies_left));
+        let headshots_text = s.assets.text(Point2::new(4., 52.)).and_text(format!("Headshots: {}", stats.headshots));
+        let enemies_text = s.assets.text(Point2::new(4., 68.)).and_text(format!("Enemies left: {}", stats.enemies_left));
         let restart_btn = Button::new(ctx, &s.assets, Rect{x: 3. * w / 7., y: 64., w: w / 7., h: 64.}, "Restart", ())?;
         let edit_btn = if let Content::File(_) = s.content {
             Some(
@@ -48,19 +50,20 @@ impl Lose {
             you_died,
             hits_text,
             misses_text,
+            headshots_text,
             enemies_text,
             restart_btn,
             edit_btn,
             level: stats.level,
             health: stats.health_left,
-            weapon: stats.weapon,
+            inventory: stats.inventory,
         }))
     }
     fn edit(&self, s: &mut State) {
         s.switch(StateSwitch::Editor(Some(self.level.clone())));
     }
     fn restart(&self, s: &mut State) {
-        s.switch(StateSwitch::PlayWith{lvl: Box::new(self.level.clone()), health: self.health, wep: self.weapon})
+        s.switch(StateSwitch::PlayWith{lvl: Box::new(self.level.clone()), health: self.health, inventory: self.inventory.clone()})
     }
 }
 
@@ -74,6 +77,7 @@ impl GameState for Lose {
         self.you_died.draw_center(ctx)?;
         self.hits_text.draw_text(ctx)?;
         self.misses_text.draw_text(ctx)?;
+        self.headshots_text.draw_text(ctx)?;
         self.enemies_text.draw_text(ctx)
     }
     fn key_up(&mut self, s: &mut State, _ctx: &mut Context, keycode: KeyCode) {
@@ -25,6 +25,12 @@ enum Callback {
     SwitchPlay(PathBuf),
     SwitchEditor,
     Campaign(PathBuf),
+    Difficulty,
+}
+
+#[inline]
+fn difficulty_text(s: &State) -> String {
+    format!("Difficulty: {}", s.difficulty)
 }
 
 // ↓
@@ -44,7 +50,7 @@ impl Menu {
         };
         s.mplayer.play(ctx, "music")?;
 
-        let buttons = match &mut s.content {
+        let mut buttons = match &mut s.content {
             Content::Campaign(_cam) => {
                 unreachable!()
             }
@@ -70,6 +76,8 @@ impl Menu {
                     .collect()
             },
         };
+        let i = buttons.len() as f32;
+        buttons.push(Button::new(ctx, &s.assets, button_rect(w, i), &difficulty_text(s), Callback::Difficulty)?);
 
         Ok(Box::new(Menu {
             title_txt: s.assets.text_sized(Point2::new(w / 2., 16.), 32.).and_text("Main Menu"),
@@ -101,8 +109,13 @@ impl GameState for Menu {
     fn mouse_up(&mut self, s: &mut State, ctx: &mut Context, btn: MouseButton) {
         use self::MouseButton::*;
         if let Left = btn {
-            for button in &self.buttons {
+            for button in &mut self.buttons {
                 if button.in_bounds(s.mouse) {
+                    if let Callback::Difficulty = button.callback {
+                        s.difficulty = s.difficulty.next();
+                        button.set_text(&difficulty_text(s)).unwrap();
+                        continue
+                    }
                     s.mplayer.stop(ctx, "music").unwrap();
                     match &button.callback {
                         Callback::Campaign(cam) => {
@@ -116,6 +129,7 @@ impl GameState for Menu {
                             s.switch(StateSwitch::Play(lvl));
                         },
                         Callback::SwitchEditor => s.switch(StateSwitch::Editor(None)),
+                        Callback::Difficulty => unreachable!(),
                     }
                 }
             }
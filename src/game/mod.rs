@@ -5,7 +5,7 @@ use crate::{
         snd::MediaPlayer,
         tex::{Assets, PosText},
     },
-    obj::{health::Health, weapon::{WEAPONS, WeaponInstance}},
+    obj::{health::Health, player::Inventory, weapon::WEAPONS, grenade::GRENADES},
 };
 use ggez::{
     nalgebra::Matrix4,
@@ -16,9 +16,14 @@ use ggez::{
 };
 use clipboard::{ClipboardContext, ClipboardProvider};
 use self::world::Level;
+use self::difficulty::Difficulty;
 
 /// Stuff related to things in the world
 pub mod world;
+/// How hard the game is
+pub mod difficulty;
+/// Gameplay that runs without a window
+pub mod sim;
 pub mod editor;
 pub mod play;
 pub mod menu;
@@ -35,7 +40,7 @@ pub enum StateSwitch {
     PlayWith{
         lvl: Box<Level>,
         health: Health,
-        wep: Option<WeaponInstance<'static>>,
+        inventory: Inventory,
     },
     Lose(Box<Statistics>),
     Win(Box<Statistics>),
@@ -63,6 +68,9 @@ pub trait GameState {
     }
     fn mouse_up(&mut self, _: &mut State, _: &mut Context, _: MouseButton) {
 
+    }
+    fn mouse_wheel(&mut self, _: &mut State, _: &mut Context, _: f32) {
+
     }
 
     fn get_world(&self) -> Option<&world::World> {
@@ -149,13 +157,13 @@ impl Console {
                     if let Ok(i) = i.parse() {
                         cmp.current = i;
                         if let Some(lvl) = cmp.next_level() {
-                            let (health, wep) = if let Some(world) = gs.get_world() {
-                                (world.player.health, world.player.wep)
+                            let (health, inventory) = if let Some(world) = gs.get_world() {
+                                (world.player.health, world.player.loadout())
                             } else {
-                                (Health::default(), None)
+                                (Health::default(), Inventory::default())
                             };
 
-                            state.switch(StateSwitch::PlayWith{health, wep, lvl: Box::new(lvl)});
+                            state.switch(StateSwitch::PlayWith{health, inventory, lvl: Box::new(lvl)});
                         } else {
                             self.history.add("Level not found\n");
                         }
@@ -169,7 +177,11 @@ impl Console {
                 self.history.add("No campaign loaded\n");
             }
             "gg" => if let Some(world) = gs.get_mut_world() {
-                world.player.utilities.grenades += 3;
+                let mut grenade_types: Vec<_> = GRENADES.values().collect();
+                grenade_types.sort_by_key(|grenade_type| &grenade_type.id);
+                for grenade_type in grenade_types {
+                    world.player.utilities.add(grenade_type, 3);
+                }
                 self.history.add("Gg'd\n");
             } else {
                 self.history.add("No world\n");
@@ -220,6 +232,7 @@ pub struct State {
     offset: Vector2,
     switch_state: Option<StateSwitch>,
     content: Content,
+    difficulty: Difficulty,
 }
 
 const DESIRED_FPS: u32 = 60;
@@ -229,7 +242,7 @@ pub(crate) const DELTA: f32 = 1. / DESIRED_FPS as f32;
 impl Master {
     #[allow(clippy::new_ret_no_self)]
     /// Make a new state object
-    pub fn new(ctx: &mut Context, arg: &str) -> GameResult<Self> {
+    pub fn new(ctx: &mut Context, arg: &str, difficulty: Difficulty) -> GameResult<Self> {
         // Initialise assets
         let assets = Assets::new(ctx)?;
         let mut mplayer = MediaPlayer::new();
@@ -249,6 +262,7 @@ impl Master {
 
         let mut state = State {
             content,
+            difficulty,
             switch_state: None,
             assets,
             mplayer,
@@ -290,7 +304,7 @@ impl EventHandler for Master {
             if let Some(gsb) = mem::replace(&mut self.state.switch_state, None) {
                 use self::StateSwitch::*;
                 self.gs = match gsb {
-                    PlayWith{lvl, health, wep} => play::Play::new(ctx, &mut self.state, *lvl, Some((health, wep))),
+                    PlayWith{lvl, health, inventory} => play::Play::new(ctx, &mut self.state, *lvl, Some((health, inventory))),
                     Play(lvl) => play::Play::new(ctx, &mut self.state, lvl, None),
                     Menu => menu::Menu::new(ctx, &mut self.state),
                     Editor(l) => editor::Editor::new(&self.state, l),
@@ -403,6 +417,10 @@ impl EventHandler for Master {
     fn mouse_button_up_event(&mut self, ctx: &mut Context, btn: MouseButton, _x: f32, _y: f32) {
         self.gs.mouse_up(&mut self.state, ctx, btn)
     }
+    /// Handles mouse wheel events
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) {
+        self.gs.mouse_wheel(&mut self.state, ctx, y)
+    }
     /// Handles mouse movement events
     fn mouse_motion_event(&mut self, _: &mut Context, x: f32, y: f32, _: f32, _: f32) {
         self.state.mouse = Point2::new(x, y);
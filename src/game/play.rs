@@ -4,15 +4,15 @@ use crate::{
         BLUE, GREEN, RED,
         angle_to_vec, angle_from_vec,
         ver, hor,
-        Vector2, Point2
+        Point2
     },
     io::tex::{Assets, PosText},
-    obj::{Object, pickup::Pickup, player::Player, enemy::{Enemy, Chaser}, health::Health, weapon::WeaponInstance, grenade::Explosion},
+    obj::{Object, pickup::Pickup, player::{Player, Hands, Inventory, EffectKind}, health::Health, weapon::WeaponInstance},
 };
 use ggez::{
     Context, GameResult,
     graphics::{
-        self, Drawable, DrawMode, Rect,
+        self, Drawable, DrawMode, Rect, Color,
         MeshBuilder, Mesh, WHITE,
         spritebatch::SpriteBatch,
     },
@@ -23,7 +23,12 @@ use ggez::{
 };
 
 use rand::{thread_rng, prelude::SliceRandom};
-use super::{DELTA, State, GameState, StateSwitch, world::{Level, Statistics, World}};
+use std::collections::HashMap;
+use super::{
+    State, GameState, StateSwitch,
+    sim::{Simulation, Input, Event},
+    world::{Level, Statistics, World},
+};
 
 #[derive(Debug, Copy, Clone)]
 enum Blood {
@@ -67,318 +72,204 @@ pub struct Play {
     arm_text: PosText,
     reload_text: PosText,
     wep_text: PosText,
+    inventory_text: PosText,
+    effects_text: PosText,
     status_text: PosText,
     hud: Hud,
-    world: World,
-    holes: SpriteBatch,
+    sim: Simulation,
+    /// Bullet holes by their sprite
+    holes: HashMap<&'static str, SpriteBatch>,
     bloods: Vec<BloodSplatter>,
-    cur_pickup: Option<usize>,
-    victory_time: f32,
     misses: usize,
-    initial: (Health, Option<WeaponInstance<'static>>),
+    headshots: usize,
+    initial: (Health, Inventory),
     level: Level,
 }
 
 impl Play {
     #[allow(clippy::new_ret_no_self)]
-    pub fn new(ctx: &mut Context, s: &mut State, level: Level, pl: Option<(Health, Option<WeaponInstance<'static>>)>) -> GameResult<Box<dyn GameState>> {
+    pub fn new(ctx: &mut Context, s: &mut State, level: Level, pl: Option<(Health, Inventory)>) -> GameResult<Box<dyn GameState>> {
         let mut player = Player::from_point(level.start_point.unwrap_or_else(|| Point2::new(500., 500.)));
-        if let Some((h, w)) = pl {
-            player = player.with_health(h).with_weapon(w);
+        if let Some((h, inv)) = pl {
+            player = player.with_health(h).with_inventory(inv);
         };
 
         Ok(Box::new(
             Play {
                 level: level.clone(),
-                initial: (player.health, player.wep),
+                initial: (player.health, player.loadout()),
                 hp_text: s.assets.text(Point2::new(4., 4.)).and_text("100"),
                 arm_text: s.assets.text(Point2::new(4., 33.)).and_text("100"),
                 reload_text: s.assets.text(Point2::new(4., 62.)).and_text("0.0").and_text("s"),
                 wep_text: WeaponInstance::weapon_text(Point2::new(2., 87.), &s.assets),
+                inventory_text: s.assets.text(Point2::new(2., 112.)).and_text(""),
+                effects_text: s.assets.text(Point2::new(2., 141.)).and_text(""),
                 status_text: s.assets.text(Point2::new(s.width as f32 / 2., s.height as f32 / 2. + 32.)).and_text(""),
                 hud: Hud::new(ctx)?,
                 misses: 0,
-                victory_time: 0.,
+                headshots: 0,
                 bloods: Vec::new(),
-                cur_pickup: None,
-                world: {
-                    let mut world = World {
-                        enemies: level.enemies,
-                        bullets: Vec::new(),
-                        grenades: Vec::new(),
-                        weapons: level.weapons,
-                        player,
-                        palette: level.palette,
-                        grid: level.grid,
-                        exit: level.exit,
-                        intels: level.intels,
-                        decorations: level.decorations,
-                        pickups: level.pickups.into_iter().map(|(p, i)| Pickup::new(p, i)).collect(),
-                    };
-                    world.enemy_pickup();
-                    world.player_pickup();
-
-                    if world.player.wep.is_none() {
-                        eprintln!("Warning: player has no weapon");
-                    }
-
-                    for enemy_pos in world.enemies.iter().filter_map(|enemy| if enemy.pl.wep.is_none() {Some(enemy.pl.obj.pos)}else{None}) {
-                        eprintln!("Warning: enemy at {:.2} has no weapon", enemy_pos)
-                    }
-
-                    world
-                },
-                holes: SpriteBatch::new(s.assets.get_img(ctx, "common/hole").clone()),
+                sim: Simulation::new(World {
+                    enemies: level.enemies,
+                    bullets: Vec::new(),
+                    grenades: Vec::new(),
+                    areas: Vec::new(),
+                    weapons: level.weapons,
+                    player,
+                    palette: level.palette,
+                    grid: level.grid,
+                    exit: level.exit,
+                    intels: level.intels,
+                    decorations: level.decorations,
+                    pickups: level.pickups.into_iter().map(|(p, pickup)| Pickup::new(p, pickup)).collect(),
+                }, s.difficulty),
+                holes: HashMap::new(),
             }
         ))
     }
-}
-
-impl GameState for Play {
-    #[allow(clippy::cognitive_complexity)]
-    fn update(&mut self, s: &mut State, ctx: &mut Context) -> GameResult<()> {
-        self.hp_text.update(0, format!("{:02.0}", self.world.player.health.hp))?;
-        self.arm_text.update(0, format!("{:02.0}", self.world.player.health.armour))?;
-        if let Some(wep) = self.world.player.wep {
-            self.reload_text.update(0, format!("{:.1}", wep.loading_time))?;
-            wep.update_text(&mut self.wep_text)?;
-        }
-        if let Some(i) = self.cur_pickup {
-            self.status_text.text.fragments_mut()[0]= format!("Press F to pick up {}", self.world.weapons[i]).into();
+    fn statistics(&self, won: bool) -> Statistics {
+        let world = &self.sim.world;
+        let (health_left, inventory) = if won {
+            (world.player.health, world.player.loadout())
         } else {
-            self.status_text.update(0, "")?;
-        }
-
-        let mut deads = Vec::new();
-        for (i, grenade) in self.world.grenades.iter_mut().enumerate().rev() {
-            let expl = grenade.update(&self.world.palette, &self.world.grid, &mut self.world.player, &mut *self.world.enemies);
-
-            if let Some(Explosion{player_hit, enemy_hits}) = expl {
-                deads.push(i);
-                s.mplayer.play(ctx, "boom")?;
-
-                if player_hit {
-                    self.bloods.push(BloodSplatter::new(self.world.player.obj.clone()));
-                    s.mplayer.play(ctx, "hit")?;
-
-                    if self.world.player.health.is_dead() {
-                        s.switch(StateSwitch::Lose(Box::new(Statistics{
-                            hits: self.bloods.len(),
-                            misses: self.misses,
-                            enemies_left: self.world.enemies.len(),
-                            health_left: self.initial.0,
-                            level: self.level.clone(),
-                            weapon: self.initial.1,
-                        })));
-                        s.mplayer.play(ctx, "death")?;
-                    } else {
-                        s.mplayer.play(ctx, "hurt")?;
-                    }
-                }
-                for i in enemy_hits {
-                    let enemy = &self.world.enemies[i];
-                    s.mplayer.play(ctx, "hit")?;
-
-                    self.bloods.push(BloodSplatter::new(enemy.pl.obj.clone()));
-                    if enemy.pl.health.is_dead() {
-                        s.mplayer.play(ctx, "death")?;
-
-                        let Enemy{pl: Player{wep, obj: Object{pos, ..}, ..}, ..}
-                            = self.world.enemies.remove(i);
-                        if let Some(wep) = wep {
-                            self.world.weapons.push(wep.into_drop(pos));
-                        }
-                    } else {
-                        if !enemy.behaviour.chasing() {
-                            self.world.enemies[i].behaviour = Chaser::LookAround{
-                                dir: grenade.obj.pos - enemy.pl.obj.pos
-                            };
-                        }
-                        s.mplayer.play(ctx, "hurt")?;
-                    }
-                }
-            }
-        }
-        for i in deads {
-            self.world.grenades.remove(i);
+            self.initial.clone()
+        };
+        Statistics{
+            hits: self.bloods.len(),
+            misses: self.misses,
+            headshots: self.headshots,
+            enemies_left: world.enemies.len(),
+            health_left,
+            level: self.level.clone(),
+            inventory,
         }
-
-        let mut deads = Vec::new();
-        for (i, bullet) in self.world.bullets.iter_mut().enumerate().rev() {
-            let hit = bullet.update(&self.world.palette, &self.world.grid, &mut self.world.player, &mut *self.world.enemies);
-            
-            use crate::obj::bullet::Hit;
-
-            match hit {
-                Hit::None => (),
-                Hit::Wall => {
-                    s.mplayer.play(ctx, &bullet.weapon.impact_snd)?;
-                    let dir = angle_to_vec(bullet.obj.rot);
-                    bullet.obj.pos += Vector2::new(5.*dir.x.signum(), 5.*dir.y.signum());
-                    self.holes.add(bullet.obj.drawparams());
+    }
+    /// Presents what has happened in the simulation
+    fn handle_events(&mut self, s: &mut State, ctx: &mut Context) -> GameResult<()> {
+        let events: Vec<_> = self.sim.events().collect();
+        for event in events {
+            match event {
+                Event::Sound(snd) => s.mplayer.play(ctx, snd)?,
+                Event::Impact(obj, hole) => {
+                    self.holes.entry(hole)
+                        .or_insert_with(|| SpriteBatch::new(s.assets.get_img(ctx, hole).clone()))
+                        .add(obj.drawparams());
                     self.misses += 1;
-                    deads.push(i);
-                }
-                Hit::Player => {
-                    deads.push(i);
-                    self.bloods.push(BloodSplatter::new(bullet.obj.clone()));
-                    s.mplayer.play(ctx, "hit")?;
-
-                    if self.world.player.health.is_dead() {
-                        s.switch(StateSwitch::Lose(Box::new(Statistics{
-                            hits: self.bloods.len(),
-                            misses: self.misses,
-                            enemies_left: self.world.enemies.len(),
-                            health_left: self.initial.0,
-                            level: self.level.clone(),
-                            weapon: self.initial.1,
-                        })));
-                        s.mplayer.play(ctx, "death")?;
-                    } else {
-                        s.mplayer.play(ctx, "hurt")?;
-                    }
-                }
-                Hit::Enemy(e) => {
-                    deads.push(i);
-                    let enemy = &self.world.enemies[e];
-                    s.mplayer.play(ctx, "hit")?;
-
-                    self.bloods.push(BloodSplatter::new(bullet.obj.clone()));
-                    if enemy.pl.health.is_dead() {
-                        s.mplayer.play(ctx, "death")?;
-
-                        let Enemy{pl: Player{wep, obj: Object{pos, ..}, ..}, ..}
-                            = self.world.enemies.remove(e);
-                        if let Some(wep) = wep {
-                            self.world.weapons.push(wep.into_drop(pos));
-                        }
-                    } else {
-                        if !enemy.behaviour.chasing() {
-                            self.world.enemies[e].behaviour = Chaser::LookAround{
-                                dir: bullet.obj.pos - enemy.pl.obj.pos
-                            };
-                        }
-                        s.mplayer.play(ctx, "hurt")?;
-                    }
                 }
+                Event::Hit(obj) => self.bloods.push(BloodSplatter::new(obj)),
+                Event::Headshot => self.headshots += 1,
+                Event::PlayerDied => s.switch(StateSwitch::Lose(Box::new(self.statistics(false)))),
+                Event::Won => s.switch(StateSwitch::Win(Box::new(self.statistics(true)))),
+                Event::Shot(_) | Event::Explosion(_) | Event::EnemyDied(_) => (),
             }
         }
-        for i in deads {
-            self.world.bullets.remove(i);
-        }
+        Ok(())
+    }
+}
 
-        let mut deads = Vec::new();
-        for (i, &intel) in self.world.intels.iter().enumerate().rev() {
-            if (intel-self.world.player.obj.pos).norm() <= 15. {
-                deads.push(i);
-                s.mplayer.play(ctx, "hit")?;
-            }
-        }
-        for i in deads {
-            self.world.intels.remove(i);
-        }
-        let mut deads = Vec::new();
-        for (i, pickup) in self.world.pickups.iter().enumerate().rev() {
-            if (pickup.pos-self.world.player.obj.pos).norm() <= 15. && pickup.apply(&mut self.world.player.health) {
-                deads.push(i);
-                s.mplayer.play(ctx, "hit")?;
-            }
-        }
-        for i in deads {
-            self.world.pickups.remove(i);
-        }
-        self.cur_pickup = None;
-        for (i, weapon) in self.world.weapons.iter().enumerate().rev() {
-            if (weapon.pos-self.world.player.obj.pos).norm() <= 29. {
-                self.cur_pickup = Some(i);
-                break
+/// The slots of the player's inventory with the one in its hands in brackets
+fn inventory_text(player: &Player) -> String {
+    let mut text = String::new();
+    for (i, &hands) in Hands::ALL.iter().enumerate() {
+        let name = match hands {
+            Hands::Weapon(slot) => {
+                let wep = if hands == player.inventory.hands {
+                    player.wep
+                } else {
+                    player.inventory.holstered[slot.index()]
+                };
+                wep.map(|wep| wep.weapon.name.to_string()).unwrap_or_else(|| "-".to_owned())
             }
+            Hands::Grenades => match player.utilities.selected() {
+                Some((grenade_type, n)) if n > 0 => format!("{} {}", n, grenade_type.name),
+                _ => "no grenades".to_owned(),
+            },
+        };
+        if hands == player.inventory.hands {
+            text.push_str(&format!("[{} {}] ", i + 1, name));
+        } else {
+            text.push_str(&format!("{} {}  ", i + 1, name));
         }
+    }
+    text
+}
 
-        // Define player velocity here already because enemies need it
-        let player_vel = Vector2::new(hor(&ctx), ver(&ctx));
-
-        for enemy in self.world.enemies.iter_mut() {
-            if enemy.can_see(self.world.player.obj.pos, &self.world.palette, &self.world.grid) {
-                enemy.behaviour = Chaser::LastKnown{
-                    pos: self.world.player.obj.pos,
-                    vel: player_vel,
-                };
+/// Dots along the path of a grenade and a ring where it goes off
+fn draw_trajectory(ctx: &mut Context, trajectory: &[Point2]) -> GameResult<()> {
+    let color = Color::new(1., 1., 1., 0.6);
+    let mut mb = MeshBuilder::new();
+    for &p in trajectory.iter().step_by(4) {
+        mb.circle(DrawMode::fill(), p, 2., 0.5, color);
+    }
+    if let Some(&end) = trajectory.last() {
+        mb.circle(DrawMode::stroke(1.5), end, 8., 0.5, color);
+    }
+    let mesh = mb.build(ctx)?;
+    graphics::draw(ctx, &mesh, graphics::DrawParam::default())
+}
 
-                if let Some(wep) = &mut enemy.pl.wep {
-                    if let Some(bm) = wep.shoot(ctx, &mut s.mplayer)? {
-                        let pos = enemy.pl.obj.pos + 20. * angle_to_vec(enemy.pl.obj.rot);
-                        let mut bul = Object::new(pos);
-                        bul.rot = enemy.pl.obj.rot;
+/// The status effects on the player with the seconds they have left
+fn effects_text(player: &Player) -> String {
+    let mut text = String::new();
+    for effect in player.effects.iter() {
+        text.push_str(&format!("{} {:.0}s  ", effect.kind.name(), effect.time.ceil()));
+    }
+    text
+}
 
-                        self.world.bullets.push(bm.make(bul));
-                    }
-                }
-            }
-            enemy.update(ctx, &mut s.mplayer)?;
+impl GameState for Play {
+    fn update(&mut self, s: &mut State, ctx: &mut Context) -> GameResult<()> {
+        let world = &self.sim.world;
+        self.hp_text.update(0, format!("{:02.0}", world.player.health.hp))?;
+        self.arm_text.update(0, format!("{:02.0}", world.player.health.armour))?;
+        if let Some(wep) = world.player.wep {
+            self.reload_text.update(0, format!("{:.1}", wep.loading_time))?;
+            wep.update_text(&mut self.wep_text, world.player.spare_ammo())?;
         }
-
-        let speed = if keyboard::is_mod_active(ctx, KeyMods::SHIFT) {
-            200.
+        self.inventory_text.update(0, inventory_text(&world.player))?;
+        self.effects_text.update(0, effects_text(&world.player))?;
+        if let Some(i) = self.sim.cur_pickup {
+            let drop = &world.weapons[i];
+            self.status_text.text.fragments_mut()[0] = if (drop.cur_clip > 0 || drop.ammo > 0) && drop.weapon.ammo_type().is_some_and(|ammo_type| world.player.uses_ammo(ammo_type)) {
+                format!("Press F to pick up {} or T to take its ammo", drop)
+            } else {
+                format!("Press F to pick up {}", drop)
+            }.into();
         } else {
-            100.
-        };
-        if let Some(wep) = &mut self.world.player.wep {
-            wep.update(ctx, &mut s.mplayer)?;
-            if wep.cur_clip > 0 && mouse::button_pressed(ctx, MouseButton::Left) && wep.weapon.fire_mode.is_auto() {
-                if let Some(bm) = wep.shoot(ctx, &mut s.mplayer)? {
-                    let pos = self.world.player.obj.pos + 20. * angle_to_vec(self.world.player.obj.rot);
-                    let mut bul = Object::new(pos);
-                    bul.rot = self.world.player.obj.rot;
-
-                    self.world.bullets.push(bm.make(bul));
-                }
-            }
+            self.status_text.update(0, "")?;
         }
-        self.world.player.obj.move_on_grid(player_vel, speed, &self.world.palette, &self.world.grid);
 
-        let game_won = match self.world.exit {
-            Some(p) => self.world.intels.is_empty() && (p - self.world.player.obj.pos).norm() < 32.,
-            None => self.world.enemies.is_empty(),
-        };
-
-        if game_won && self.victory_time <= 0. {
-            s.mplayer.play(ctx, "victory")?;
-            self.victory_time += DELTA;
-        } else if self.victory_time > 0. {
-            self.victory_time += DELTA;
-        }
-        if self.victory_time >= 2. {
-            s.switch(StateSwitch::Win(Box::new(Statistics{
-                level: self.level.clone(),
-                hits: self.bloods.len(),
-                misses: self.misses,
-                enemies_left: self.world.enemies.len(),
-                health_left: self.world.player.health,
-                weapon: self.world.player.wep,
-            })));
-        }
-        Ok(())
+        self.sim.tick(Input {
+            hor: hor(&ctx),
+            ver: ver(&ctx),
+            sprint: keyboard::is_mod_active(ctx, KeyMods::SHIFT),
+            trigger: mouse::button_pressed(ctx, MouseButton::Left),
+        });
+        self.handle_events(s, ctx)
     }
     fn logic(&mut self, s: &mut State, ctx: &mut Context) -> GameResult<()> {
-        let dist = s.mouse - s.offset - self.world.player.obj.pos;
+        let player = &mut self.sim.world.player;
+        let dist = s.mouse - s.offset - player.obj.pos;
 
-        self.hud.update_bars(ctx, &self.world.player)?;
+        self.hud.update_bars(ctx, player)?;
 
-        self.world.player.obj.rot = angle_from_vec(dist);
+        player.obj.rot = angle_from_vec(dist);
 
         // Center the camera on the player
-        let p = self.world.player.obj.pos;
+        let p = player.obj.pos;
         s.focus_on(p);
         Ok(())
     }
 
     fn draw(&mut self, s: &State, ctx: &mut Context) -> GameResult<()> {
-        self.world.grid.draw(&self.world.palette, ctx, &s.assets)?;
+        let world = &self.sim.world;
+        world.grid.draw(&world.palette, ctx, &s.assets)?;
 
-        self.holes.draw(ctx, Default::default())?;
+        for holes in self.holes.values() {
+            holes.draw(ctx, Default::default())?;
+        }
 
-        for &intel in &self.world.intels {
+        for &intel in &world.intels {
             let drawparams = graphics::DrawParam {
                 dest: intel.into(),
                 offset: Point2::new(0.5, 0.5).into(),
@@ -387,7 +278,7 @@ impl GameState for Play {
             let img = s.assets.get_img(ctx, "common/intel");
             graphics::draw(ctx, &*img, drawparams)?;
         }
-        for decoration in &self.world.decorations {
+        for decoration in &world.decorations {
             decoration.draw(ctx, &s.assets, WHITE)?;
         }
 
@@ -395,16 +286,16 @@ impl GameState for Play {
             blood.draw(ctx, &s.assets)?;
         }
 
-        for pickup in &self.world.pickups {
+        for pickup in &world.pickups {
             let drawparams = graphics::DrawParam {
                 dest: pickup.pos.into(),
                 offset: Point2::new(0.5, 0.5).into(),
                 .. Default::default()
             };
-            let img = s.assets.get_img(ctx, pickup.pickup_type.spr);
+            let img = s.assets.get_img(ctx, &pickup.pickup_type.spr);
             graphics::draw(ctx, &*img, drawparams)?;
         }
-        for wep in &self.world.weapons {
+        for wep in &world.weapons {
             let drawparams = graphics::DrawParam {
                 dest: wep.pos.into(),
                 offset: Point2::new(0.5, 0.5).into(),
@@ -414,27 +305,43 @@ impl GameState for Play {
             graphics::draw(ctx, &*img, drawparams)?;
         }
 
-        self.world.player.draw_player(ctx, &s.assets)?;
+        world.player.draw_player(ctx, &s.assets)?;
 
-        for enemy in &self.world.enemies {
+        for enemy in &world.enemies {
             enemy.draw(ctx, &s.assets, WHITE)?;
+            enemy.draw_awareness(ctx)?;
         }
-        for bullet in &self.world.bullets {
+        for bullet in &world.bullets {
             bullet.draw(ctx, &s.assets)?;
         }
-        for grenade in &self.world.grenades {
+        for grenade in &world.grenades {
             grenade.draw(ctx, &s.assets)?;
         }
+        for area in &world.areas {
+            area.draw(ctx)?;
+        }
+        if let Some(trajectory) = self.sim.grenade_trajectory() {
+            draw_trajectory(ctx, &trajectory)?;
+        }
 
         Ok(())
     }
     fn draw_hud(&mut self, s: &State, ctx: &mut Context) -> GameResult<()> {
+        // Being stunned whites out the screen, fading in the last second
+        if let Some(stun) = self.sim.world.player.effects.get(EffectKind::Stunned) {
+            let alpha = stun.strength.limit(0., 1.) * stun.time.min(1.);
+            let screen = Rect::new(0., 0., s.width as f32, s.height as f32);
+            let flash = Mesh::new_rectangle(ctx, DrawMode::fill(), screen, Color::new(1., 1., 1., alpha))?;
+            graphics::draw(ctx, &flash, graphics::DrawParam::default())?;
+        }
         self.hud.draw(ctx)?;
 
         self.hp_text.draw_text(ctx)?;
         self.arm_text.draw_text(ctx)?;
         self.reload_text.draw_text(ctx)?;
         self.wep_text.draw_text(ctx)?;
+        self.inventory_text.draw_text(ctx)?;
+        self.effects_text.draw_text(ctx)?;
         self.status_text.draw_center(ctx)?;
 
         let drawparams = graphics::DrawParam {
@@ -446,63 +353,47 @@ impl GameState for Play {
         let img = s.assets.get_img(ctx, "common/crosshair");
         graphics::draw(ctx, &*img, drawparams)
     }
+    fn mouse_down(&mut self, _s: &mut State, _ctx: &mut Context, btn: MouseButton) {
+        match btn {
+            MouseButton::Left if self.sim.world.player.inventory.hands == Hands::Grenades => self.sim.cook_grenade(),
+            MouseButton::Right => self.sim.cook_grenade(),
+            _ => (),
+        }
+    }
     fn mouse_up(&mut self, s: &mut State, ctx: &mut Context, btn: MouseButton) {
         match btn {
-            MouseButton::Left => {
-                if let Some(wep) = &mut self.world.player.wep {
-                    if let Some(bm) = wep.shoot(ctx, &mut s.mplayer).unwrap() {
-                        let pos = self.world.player.obj.pos + 20. * angle_to_vec(self.world.player.obj.rot);
-                        let mut bul = Object::new(pos);
-                        bul.rot = self.world.player.obj.rot;
-
-                        self.world.bullets.push(bm.make(bul));
-                    }
-                }
-            }
-            MouseButton::Right => {
-                if let Some(gm) = self.world.player.utilities.throw_grenade(ctx, &mut s.mplayer).unwrap() {
-                    let pos = self.world.player.obj.pos + 20. * angle_to_vec(self.world.player.obj.rot);
-                    let mut gren = Object::new(pos);
-                    gren.rot = self.world.player.obj.rot;
-
-                    self.world.grenades.push(gm.make(gren));
-                }
-            }
+            MouseButton::Left => self.sim.shoot(),
+            MouseButton::Right => self.sim.throw_grenade(),
             _ => (),
         }
+        self.handle_events(s, ctx).unwrap();
     }
     fn key_up(&mut self, s: &mut State, ctx: &mut Context, keycode: KeyCode) {
         use self::KeyCode::*;
         match keycode {
-            R => {
-                if let Some(wep) = &mut self.world.player.wep {
-                    wep.reload(ctx, &mut s.mplayer).unwrap()
-                } else {
-                    self.world.bullets.push(crate::obj::bullet::Bullet{obj: self.world.player.obj.clone(), weapon: &crate::obj::weapon::WEAPONS["glock"]});
-                }
-            },
-            F => {
-                if let Some(i) = self.cur_pickup {
-                    self.world.player.wep = Some(WeaponInstance::from_drop(
-                        if let Some(wep) = self.world.player.wep {
-                            let w = wep.into_drop(self.world.player.obj.pos);
-                            std::mem::replace(&mut self.world.weapons[i], w)
-                        } else {
-                            self.world.weapons.remove(i)
-                        }
-                    ));
-                    self.cur_pickup = None;
-                }
-            },
+            R => self.sim.reload(),
+            F => self.sim.pick_up(),
+            T => self.sim.strip_ammo(),
+            G => self.sim.cycle_grenades(),
+            Key1 => self.sim.switch_to(Hands::ALL[0]),
+            Key2 => self.sim.switch_to(Hands::ALL[1]),
+            Key3 => self.sim.switch_to(Hands::ALL[2]),
+            Key4 => self.sim.switch_to(Hands::ALL[3]),
             _ => (),
         }
+        self.handle_events(s, ctx).unwrap();
+    }
+    fn mouse_wheel(&mut self, _s: &mut State, _ctx: &mut Context, y: f32) {
+        if y != 0. {
+            self.sim.cycle_weapons(y < 0.);
+        }
     }
 
     fn get_world(&self) -> Option<&World> {
-        Some(&self.world)
+        Some(&self.sim.world)
     }
     fn get_mut_world(&mut self) -> Option<&mut World> {
-        Some(&mut self.world)
+        Some(&mut self.sim.world)
     }
 }
 
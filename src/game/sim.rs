@@ -0,0 +1,807 @@
+use crate::{
+    util::{angle_to_vec, angle_from_vec, Vector2, Point2},
+    obj::{
+        Object, BODY_RADIUS,
+        player::{Player, Hands},
+        enemy::{Enemy, Awareness, Chaser},
+        bullet::{Bullet, Hit, BodyPart, Targets},
+        grenade::{Grenade, GrenadeType, Explosion, Detonation},
+        weapon::{WEAPONS, Weapon},
+    },
+};
+
+use rand::{thread_rng, Rng, SeedableRng, rngs::StdRng};
+
+use super::{DELTA, difficulty::Difficulty, world::{World, Noise, impact_snd, bullet_hole}};
+
+/// How far away a bullet hitting a wall can be heard
+const IMPACT_LOUDNESS: f32 = 300.;
+/// How far away the player's footsteps can be heard when running
+const FOOTSTEP_LOUDNESS: f32 = 224.;
+/// How far away an alerted enemy can be heard calling for help
+const CALL_LOUDNESS: f32 = 480.;
+/// Pixels per second the player walks
+const WALK_SPEED: f32 = 100.;
+/// Pixels per second the player runs
+const SPRINT_SPEED: f32 = 200.;
+
+/// Something that happened in the simulation which the front end might want to present
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A sound effect (or music) should be played
+    Sound(&'static str),
+    /// A bullet was fired from the object
+    Shot(Object),
+    /// A bullet hit a wall and left a hole with the sprite where the object is
+    Impact(Object, &'static str),
+    /// Someone was hit and bled where the object is
+    Hit(Object),
+    /// An enemy was shot in the head
+    Headshot,
+    /// A grenade exploded
+    Explosion(Point2),
+    /// An enemy died at the point
+    EnemyDied(Point2),
+    /// The player has died
+    PlayerDied,
+    /// The level has been won and the victory music has played
+    Won,
+}
+
+/// The player's input during a single tick
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Input {
+    /// Horizontal movement, from -1 (left) to 1 (right)
+    pub hor: f32,
+    /// Vertical movement, from -1 (up) to 1 (down)
+    pub ver: f32,
+    /// Whether the player is running
+    pub sprint: bool,
+    /// Whether the trigger is held down, only used by automatic weapons
+    pub trigger: bool,
+}
+
+impl Input {
+    #[inline]
+    pub fn vel(self) -> Vector2 {
+        Vector2::new(self.hor, self.ver)
+    }
+    #[inline]
+    pub fn speed(self) -> f32 {
+        if self.sprint {
+            SPRINT_SPEED
+        } else {
+            WALK_SPEED
+        }
+    }
+}
+
+/// The gameplay of a level, advanced one `DELTA` at a time
+///
+/// Nothing in here needs a `Context`, so it can be run without a window or audio device.
+/// Anything that should be seen or heard is reported as an `Event` instead.
+#[derive(Debug)]
+pub struct Simulation {
+    pub world: World,
+    /// The weapon drop the player is close enough to pick up
+    pub cur_pickup: Option<usize>,
+    pub victory_time: f32,
+    pub difficulty: Difficulty,
+    events: Vec<Event>,
+    /// Noises made since the enemies last listened
+    noises: Vec<Noise>,
+    /// Alerted enemies calling out where the player is
+    calls: Vec<(Noise, Point2)>,
+    /// Decides where the enemies aim and what ricochets, so the same seed and inputs play out the same way
+    rng: StdRng,
+}
+
+impl Simulation {
+    pub fn new(world: World, difficulty: Difficulty) -> Self {
+        Self::with_seed(world, difficulty, thread_rng().gen())
+    }
+    /// Like `new` but with the seed for everything random in the simulation given
+    pub fn with_seed(mut world: World, difficulty: Difficulty, seed: u64) -> Self {
+        // Spare ammo of the weapons lying around and held by the enemies
+        let ammo = world.weapons.iter_mut().map(|drop| &mut drop.ammo)
+            .chain(world.enemies.iter_mut().filter_map(|enemy| enemy.pl.wep.as_mut()).map(|wep| &mut wep.ammo));
+        for ammo in ammo {
+            *ammo = (f32::from(*ammo) * difficulty.ammo()).round() as u16;
+        }
+
+        world.enemy_pickup();
+        world.player_pickup(difficulty.pickups());
+
+        if world.player.wep.is_none() {
+            eprintln!("Warning: player has no weapon");
+        }
+
+        for enemy_pos in world.enemies.iter().filter_map(|enemy| if enemy.pl.wep.is_none() {Some(enemy.pl.obj.pos)}else{None}) {
+            eprintln!("Warning: enemy at {:.2} has no weapon", enemy_pos)
+        }
+
+        Simulation {
+            world,
+            cur_pickup: None,
+            victory_time: 0.,
+            difficulty,
+            events: Vec::new(),
+            noises: Vec::new(),
+            calls: Vec::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+    /// Takes the events that have happened since the last time this was called
+    #[inline]
+    pub fn events(&mut self) -> ::std::vec::Drain<'_, Event> {
+        self.events.drain(..)
+    }
+    /// Advances the simulation by one `DELTA`
+    pub fn tick(&mut self, input: Input) {
+        self.update_grenades();
+        self.update_areas();
+        self.update_bullets();
+        self.update_pickups();
+        self.update_effects();
+        let vel = input.vel();
+        let speed = input.speed() * self.world.player.effects.speed_factor();
+        let player_vel = if vel.norm_squared() != 0. { vel.normalize() * speed } else { vel };
+        self.update_enemies(player_vel);
+        self.update_player(input);
+        self.update_victory();
+    }
+    /// Fires a single shot with the player's weapon, or throws a grenade if it's holding those
+    ///
+    /// Melee weapons are swung instead, and without a weapon the player punches.
+    pub fn shoot(&mut self) {
+        if self.world.player.inventory.hands == Hands::Grenades {
+            return self.throw_grenade()
+        }
+        let player = &mut self.world.player;
+        if player.wep.map(|wep| wep.weapon.swing.is_some()).unwrap_or(true) {
+            if let Some(weapon) = player.swing(&mut self.events) {
+                self.player_melee(weapon);
+            }
+            return
+        }
+        if let Some(wep) = &mut player.wep {
+            if let Some(bm) = wep.shoot(&mut self.events) {
+                let pos = player.obj.pos + 20. * angle_to_vec(player.obj.rot);
+                let bul = Object::with_rot(pos, player.obj.rot);
+
+                self.noises.push(Noise::new(pos, wep.weapon.loudness));
+                self.events.push(Event::Shot(bul.clone()));
+                self.world.bullets.extend(bm.make(bul));
+            }
+        }
+    }
+    /// Starts charging a grenade throw, the throw happens with `throw_grenade`
+    pub fn cook_grenade(&mut self) {
+        self.world.player.utilities.cook();
+    }
+    pub fn throw_grenade(&mut self) {
+        let player = &mut self.world.player;
+        if let Some(gm) = player.utilities.throw_grenade(&mut self.events) {
+            self.world.grenades.push(gm.make(grenade_start(player)));
+        }
+        if player.inventory.hands == Hands::Grenades && !player.has(Hands::Grenades) {
+            player.cycle(true);
+        }
+    }
+    /// Where the grenade the player would throw now would go, if it's holding grenades
+    pub fn grenade_trajectory(&self) -> Option<Vec<Point2>> {
+        let player = &self.world.player;
+        if player.inventory.hands != Hands::Grenades && player.utilities.cook_time.is_none() {
+            return None
+        }
+        let grenade = player.utilities.aim()?.make(grenade_start(player));
+        Some(grenade.trajectory(&self.world.palette, &self.world.grid))
+    }
+    /// Selects the next type of grenade the player has
+    pub fn cycle_grenades(&mut self) {
+        self.world.player.utilities.cycle();
+    }
+    /// Takes the ammo out of the weapon the player is standing on if it carries a weapon of the same calibre
+    pub fn strip_ammo(&mut self) {
+        if let Some(i) = self.cur_pickup {
+            let drop = &mut self.world.weapons[i];
+            let player = &mut self.world.player;
+            let ammo = drop.cur_clip.saturating_add(drop.ammo);
+            if let Some(ammo_type) = drop.weapon.ammo_type().filter(|&ammo_type| ammo > 0 && player.uses_ammo(ammo_type)) {
+                player.inventory.add_ammo(ammo_type, ammo);
+                drop.cur_clip = 0;
+                drop.ammo = 0;
+                self.events.push(Event::Sound(&drop.weapon.reload_snd));
+            }
+        }
+    }
+    /// Switches what the player has in its hands
+    #[inline]
+    pub fn switch_to(&mut self, hands: Hands) {
+        self.world.player.switch_to(hands)
+    }
+    /// Switches to the player's next (or previous) weapon
+    #[inline]
+    pub fn cycle_weapons(&mut self, forward: bool) {
+        self.world.player.cycle(forward)
+    }
+    pub fn reload(&mut self) {
+        if self.world.player.wep.is_some() {
+            self.world.player.reload(&mut self.events)
+        } else {
+            self.world.bullets.push(Bullet::new(self.world.player.obj.clone(), &WEAPONS["glock"]));
+        }
+    }
+    /// Picks up the weapon the player is standing on, dropping the one it had in the same slot
+    pub fn pick_up(&mut self) {
+        if let Some(i) = self.cur_pickup {
+            let drop = self.world.weapons.remove(i);
+            if let Some(old) = self.world.player.pick_up(drop) {
+                self.world.weapons.insert(i, old);
+            }
+            self.cur_pickup = None;
+        }
+    }
+
+    /// The player hits the closest enemy in reach, taking it down at once if it can
+    fn player_melee(&mut self, weapon: &'static Weapon) {
+        let swing = match weapon.swing {
+            Some(swing) => swing,
+            None => return,
+        };
+        let World{player, enemies, palette, grid, ..} = &mut self.world;
+        let from = player.obj.pos;
+        self.noises.push(Noise::new(from, weapon.loudness));
+
+        let mut target = None;
+        let mut closest = std::f32::INFINITY;
+        for (i, enemy) in enemies.iter().enumerate() {
+            let dist = (enemy.pl.obj.pos - from).norm();
+            if dist < closest && swing.reaches(&player.obj, enemy.pl.obj.pos, palette, grid) {
+                target = Some(i);
+                closest = dist;
+            }
+        }
+        if let Some(e) = target {
+            let enemy = &mut enemies[e];
+            if enemy.can_be_taken_down(from) {
+                enemy.pl.health.hp = 0.;
+            } else {
+                let scale = enemy.pl.effects.damage_factor();
+                weapon.hurt(&mut enemy.pl, weapon.damage * scale);
+            }
+            let at = enemies[e].pl.obj.clone();
+            self.enemy_hit(e, at, from);
+        }
+    }
+    /// Factor on the damage the player takes
+    fn player_damage(&self) -> f32 {
+        self.difficulty.damage_taken() * self.world.player.effects.damage_factor()
+    }
+    /// Presents an enemy being hurt where the object is, removing it if it died or making it look towards the point if it didn't
+    fn enemy_hit(&mut self, e: usize, at: Object, from: Point2) {
+        self.events.push(Event::Sound("hit"));
+        self.events.push(Event::Hit(at));
+
+        if !self.remove_if_dead(e) {
+            let enemy = &mut self.world.enemies[e];
+            if !enemy.behaviour.chasing() {
+                enemy.investigate(from);
+            }
+            enemy.alert();
+            self.events.push(Event::Sound("hurt"));
+        }
+    }
+    /// Removes the enemy if it has died, leaving its weapon behind, returns whether it did
+    fn remove_if_dead(&mut self, e: usize) -> bool {
+        if !self.world.enemies[e].pl.health.is_dead() {
+            return false
+        }
+        self.events.push(Event::Sound("death"));
+
+        let Enemy{pl: Player{wep, obj: Object{pos, ..}, ..}, ..}
+            = self.world.enemies.remove(e);
+        self.events.push(Event::EnemyDied(pos));
+        if let Some(wep) = wep {
+            self.world.weapons.push(wep.into_drop(pos));
+        }
+        true
+    }
+
+    fn update_grenades(&mut self) {
+        let player_damage = self.player_damage();
+        let mut deads = Vec::new();
+        let mut blasts = Vec::new();
+        for i in (0..self.world.grenades.len()).rev() {
+            let World{grenades, palette, grid, player, enemies, ..} = &mut self.world;
+            let grenade = &mut grenades[i];
+            let detonation = grenade.update(palette, grid, player, player_damage, enemies);
+            let grenade_type = grenade.grenade_type;
+            let pos = grenade.obj.pos;
+            if detonation.is_some() {
+                deads.push(i);
+                self.events.push(Event::Sound(&grenade_type.sound));
+                self.noises.push(Noise::new(pos, grenade_type.loudness));
+            }
+
+            match detonation {
+                None => (),
+                Some(Detonation::Area(area)) => self.world.areas.push(area),
+                Some(Detonation::Flash(Explosion{enemy_hits, ..})) => {
+                    self.events.push(Event::Explosion(pos));
+                    for i in enemy_hits {
+                        let enemy = &mut self.world.enemies[i];
+                        if !enemy.behaviour.chasing() {
+                            enemy.investigate(pos);
+                        }
+                        enemy.alert();
+                    }
+                }
+                Some(Detonation::Explosion(Explosion{player_hit, enemy_hits})) => {
+                    self.events.push(Event::Explosion(pos));
+                    blasts.push((pos, grenade_type));
+
+                    if player_hit {
+                        hit_player(&mut self.events, &self.world.player, self.world.player.obj.clone());
+                    }
+                    // The hits are from the back, so removing the dead doesn't move the others
+                    for e in enemy_hits {
+                        let at = self.world.enemies[e].pl.obj.clone();
+                        self.enemy_hit(e, at, pos);
+                    }
+                }
+            }
+        }
+        for i in deads {
+            self.world.grenades.remove(i);
+        }
+        for (pos, grenade_type) in blasts {
+            self.blast_props(pos, grenade_type);
+        }
+    }
+    /// Explosions damage the props they reach, which can set off explosive ones
+    fn blast_props(&mut self, pos: Point2, grenade_type: &'static GrenadeType) {
+        let World{decorations, palette, grid, ..} = &mut self.world;
+        let mut destroyed = Vec::new();
+        for (i, dec) in decorations.iter_mut().enumerate() {
+            if dec.take_damage(grenade_type.prop_damage(palette, grid, pos, dec.obj.pos)) {
+                destroyed.push(i);
+            }
+        }
+        self.destroy_props(destroyed);
+    }
+    /// Props that were just destroyed break, and explosive ones go off shortly after, which can set off more of them
+    fn destroy_props(&mut self, mut destroyed: Vec<usize>) {
+        destroyed.sort_unstable();
+        destroyed.dedup();
+        for i in destroyed.into_iter().rev() {
+            let dec = &self.world.decorations[i];
+            self.events.push(Event::Sound("impact"));
+            self.noises.push(Noise::new(dec.obj.pos, IMPACT_LOUDNESS));
+            if let Some(explosive) = dec.explosive() {
+                self.world.grenades.push(Grenade {
+                    obj: dec.obj.clone(),
+                    vel: Vector2::new(0., 0.),
+                    fuse: explosive.fuse,
+                    grenade_type: explosive,
+                });
+            }
+            if dec.vanishes() {
+                self.world.decorations.remove(i);
+            }
+        }
+    }
+    /// Counts down smoke clouds and fires, setting the ones in fires burning
+    fn update_areas(&mut self) {
+        let World{areas, player, enemies, ..} = &mut self.world;
+        for area in areas.iter_mut() {
+            area.update(player, enemies);
+        }
+        areas.retain(|area| area.time > 0.);
+    }
+    fn update_bullets(&mut self) {
+        let player_damage = self.player_damage();
+        let mut deads = Vec::new();
+        let mut destroyed = Vec::new();
+        for i in (0..self.world.bullets.len()).rev() {
+            let World{bullets, palette, grid, player, enemies, decorations, ..} = &mut self.world;
+            let bullet = &mut bullets[i];
+            let hit = bullet.update(palette, grid, Targets{player, player_damage, enemies, decorations}, &mut self.rng);
+            let (obj, weapon) = (bullet.obj.clone(), bullet.weapon);
+
+            match hit {
+                Hit::None => (),
+                Hit::Wall{at, mat, through} => {
+                    let dir = angle_to_vec(obj.rot);
+                    let hole = Object::with_rot(at + Vector2::new(5.*dir.x.signum(), 5.*dir.y.signum()), obj.rot);
+                    impact(&mut self.events, &mut self.noises, weapon, hole, mat);
+                    if !through {
+                        deads.push(i);
+                    }
+                }
+                Hit::Ricochet{at, mat, to_wall} => {
+                    let hole = Object::with_rot(at + 5. * to_wall, angle_from_vec(to_wall));
+                    impact(&mut self.events, &mut self.noises, weapon, hole, mat);
+                }
+                Hit::Prop{index, destroyed: broke} => {
+                    self.events.push(Event::Sound(&weapon.impact_snd));
+                    self.noises.push(Noise::new(obj.pos, IMPACT_LOUDNESS));
+                    deads.push(i);
+                    if broke {
+                        destroyed.push(index);
+                    }
+                }
+                Hit::Player(_) => {
+                    deads.push(i);
+                    hit_player(&mut self.events, &self.world.player, obj.clone());
+                }
+                Hit::Enemy(e, part) => {
+                    deads.push(i);
+                    if part == BodyPart::Head {
+                        self.events.push(Event::Headshot);
+                    }
+                    self.enemy_hit(e, obj.clone(), obj.pos);
+                }
+            }
+
+            // Explosive projectiles go off at whatever they hit
+            if let Some(explosion) = &weapon.explosion {
+                let pos = match hit {
+                    Hit::None => continue,
+                    Hit::Wall{at, ..} => at - angle_to_vec(obj.rot),
+                    Hit::Ricochet{at, to_wall, ..} => at - to_wall,
+                    _ => obj.pos,
+                };
+                if deads.last() != Some(&i) {
+                    deads.push(i);
+                }
+                self.world.grenades.push(Grenade {
+                    obj: Object::with_rot(pos, obj.rot),
+                    vel: Vector2::new(0., 0.),
+                    fuse: 0.,
+                    grenade_type: explosion,
+                });
+            }
+        }
+        for i in deads {
+            self.world.bullets.remove(i);
+        }
+        self.destroy_props(destroyed);
+    }
+    fn update_pickups(&mut self) {
+        let mut deads = Vec::new();
+        for (i, &intel) in self.world.intels.iter().enumerate().rev() {
+            if (intel-self.world.player.obj.pos).norm() <= 15. {
+                deads.push(i);
+                self.events.push(Event::Sound("hit"));
+            }
+        }
+        for i in deads {
+            self.world.intels.remove(i);
+        }
+        let mut deads = Vec::new();
+        for (i, pickup) in self.world.pickups.iter().enumerate().rev() {
+            if (pickup.pos-self.world.player.obj.pos).norm() <= 15. && pickup.apply(&mut self.world.player, self.difficulty.pickups()) {
+                deads.push(i);
+                self.events.push(Event::Sound("hit"));
+            }
+        }
+        for i in deads {
+            self.world.pickups.remove(i);
+        }
+        // Enemies only take the pickups they're going for
+        for enemy in &mut self.world.enemies {
+            if let Chaser::Heal{pos} = enemy.behaviour {
+                if (pos - enemy.pl.obj.pos).norm() > BODY_RADIUS {
+                    continue
+                }
+                if let Some(i) = self.world.pickups.iter().position(|pickup| pickup.pos == pos) {
+                    if self.world.pickups[i].apply(&mut enemy.pl, 1.) {
+                        self.world.pickups.remove(i);
+                        self.events.push(Event::Sound("hit"));
+                    }
+                }
+                enemy.behaviour = Chaser::NoIntel;
+            }
+        }
+        self.cur_pickup = None;
+        for (i, weapon) in self.world.weapons.iter().enumerate().rev() {
+            if (weapon.pos-self.world.player.obj.pos).norm() <= 29. {
+                self.cur_pickup = Some(i);
+                break
+            }
+        }
+    }
+    /// Deals the damage of status effects and counts them down
+    fn update_effects(&mut self) {
+        let player = &mut self.world.player;
+        let alive = !player.health.is_dead();
+        if player.effects.update(&mut player.health, self.difficulty.damage_taken()) && alive && player.health.is_dead() {
+            self.events.push(Event::PlayerDied);
+            self.events.push(Event::Sound("death"));
+        }
+        for i in (0..self.world.enemies.len()).rev() {
+            let enemy = &mut self.world.enemies[i];
+            if enemy.pl.effects.update(&mut enemy.pl.health, 1.) {
+                self.remove_if_dead(i);
+            }
+        }
+    }
+    fn update_enemies(&mut self, player_vel: Vector2) {
+        let noises = std::mem::replace(&mut self.noises, Vec::new());
+        let calls = std::mem::replace(&mut self.calls, Vec::new());
+        let player_damage = self.player_damage();
+        let difficulty = self.difficulty;
+        let World{enemies, player, bullets, decorations, pickups, areas, palette, grid, ..} = &mut self.world;
+        let player_pos = player.obj.pos;
+
+        for enemy in enemies.iter_mut() {
+            enemy.pl.fists_time = (enemy.pl.fists_time - DELTA).max(0.);
+            enemy.hear(&noises, palette, grid);
+            enemy.hear_calls(&calls, palette, grid);
+
+            // Stunned enemies are blinded
+            let sees = !enemy.pl.effects.stunned() && enemy.can_see(player_pos, difficulty, areas, palette, grid);
+            enemy.watch(if sees { Some((player_pos, player_vel)) } else { None }, difficulty, &mut self.rng);
+            enemy.think(pickups, palette, grid);
+
+            if sees && enemy.awareness == Awareness::Alerted {
+                self.calls.push((Noise::new(enemy.pl.obj.pos, CALL_LOUDNESS), player_pos));
+
+                // Enemies with nothing to shoot go for the player with what they have
+                let melee = enemy.pl.wep.map(|wep| wep.weapon.swing.is_some() || (wep.cur_clip == 0 && wep.ammo == 0)).unwrap_or(true);
+                if let Some(rot) = enemy.aim(player_pos, player_vel, difficulty, &mut self.rng) {
+                    if melee {
+                        let reaches = enemy.pl.melee_weapon().swing
+                            .map(|swing| swing.reaches(&enemy.pl.obj, player_pos, palette, grid))
+                            .unwrap_or(false);
+                        if reaches {
+                            if let Some(weapon) = enemy.pl.swing(&mut self.events) {
+                                self.noises.push(Noise::new(enemy.pl.obj.pos, weapon.loudness));
+                                weapon.hurt(player, weapon.damage * player_damage);
+                                hit_player(&mut self.events, player, player.obj.clone());
+                            }
+                        }
+                    } else if let Some(wep) = &mut enemy.pl.wep {
+                        if let Some(bm) = wep.shoot(&mut self.events) {
+                            let pos = enemy.pl.obj.pos + 20. * angle_to_vec(enemy.pl.obj.rot);
+                            let bul = Object::with_rot(pos, rot);
+
+                            self.noises.push(Noise::new(pos, wep.weapon.loudness));
+                            self.events.push(Event::Shot(bul.clone()));
+                            bullets.extend(bm.make(bul));
+                        }
+                    }
+                }
+            }
+            enemy.update(palette, grid, &mut self.events);
+            for dec in decorations.iter() {
+                dec.push_out(&mut enemy.pl.obj, BODY_RADIUS, palette, grid);
+            }
+        }
+    }
+    fn update_player(&mut self, input: Input) {
+        let speed = input.speed() * self.world.player.effects.speed_factor();
+        self.world.player.fists_time = (self.world.player.fists_time - DELTA).max(0.);
+        // Holding on to a grenade too long throws it right as it goes off
+        if self.world.player.utilities.update() {
+            self.throw_grenade();
+        }
+        let mut fire = false;
+        if let Some(wep) = &mut self.world.player.wep {
+            wep.update(&mut self.events);
+            fire = wep.cur_clip > 0 && input.trigger && wep.weapon.fire_mode.is_auto();
+        }
+        if fire {
+            self.shoot();
+        }
+        if input.sprint && input.vel() != Vector2::new(0., 0.) {
+            self.noises.push(Noise::new(self.world.player.obj.pos, FOOTSTEP_LOUDNESS));
+        }
+        let World{player, decorations, palette, grid, ..} = &mut self.world;
+        player.obj.move_on_grid(input.vel(), speed, palette, grid);
+        for dec in decorations.iter() {
+            dec.push_out(&mut player.obj, BODY_RADIUS, palette, grid);
+        }
+    }
+    fn update_victory(&mut self) {
+        let game_won = match self.world.exit {
+            Some(p) => self.world.intels.is_empty() && (p - self.world.player.obj.pos).norm() < 32.,
+            None => self.world.enemies.is_empty(),
+        };
+
+        if game_won && self.victory_time <= 0. {
+            self.events.push(Event::Sound("victory"));
+            self.victory_time += DELTA;
+        } else if self.victory_time > 0. {
+            self.victory_time += DELTA;
+        }
+        if self.victory_time >= 2. {
+            self.events.push(Event::Won);
+        }
+    }
+}
+
+/// A bullet hitting a wall of the material is heard and leaves a hole where the object is
+fn impact(events: &mut Vec<Event>, noises: &mut Vec<Noise>, weapon: &'static Weapon, hole: Object, mat: &'static str) {
+    events.push(Event::Sound(impact_snd(mat).unwrap_or(&*weapon.impact_snd)));
+    noises.push(Noise::new(hole.pos, IMPACT_LOUDNESS));
+    events.push(Event::Impact(hole, bullet_hole(mat)));
+}
+
+/// Where a grenade thrown by the player starts
+fn grenade_start(player: &Player) -> Object {
+    let pos = player.obj.pos + 20. * angle_to_vec(player.obj.rot);
+    Object::with_rot(pos, player.obj.rot)
+}
+
+fn hit_player(events: &mut Vec<Event>, player: &Player, at: Object) {
+    events.push(Event::Hit(at));
+    events.push(Event::Sound("hit"));
+
+    if player.health.is_dead() {
+        events.push(Event::PlayerDied);
+        events.push(Event::Sound("death"));
+    } else {
+        events.push(Event::Sound("hurt"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::world::{Grid, Palette},
+        obj::{grenade::{Grenade, GRENADES}, player::EffectKind},
+    };
+
+    /// An open field with the player in the middle, holding a pistol
+    fn world() -> World {
+        let mut player = Player::from_point(Point2::new(256., 256.));
+        player.wep = Some(WEAPONS["glock"].make_instance());
+        World {
+            player,
+            palette: Palette::default(),
+            grid: Grid::new(16, 16),
+            exit: None,
+            intels: Vec::new(),
+            enemies: Vec::new(),
+            bullets: Vec::new(),
+            grenades: Vec::new(),
+            weapons: Vec::new(),
+            decorations: Vec::new(),
+            pickups: Vec::new(),
+            areas: Vec::new(),
+        }
+    }
+    fn enemy_at(pos: Point2, hp: f32) -> Enemy {
+        let mut enemy = Enemy::new(Object::new(pos));
+        enemy.pl.health.hp = hp;
+        enemy.pl.health.armour = 0.;
+        enemy
+    }
+    /// Ticks the simulation without any input until an event matches, returns whether one did
+    fn tick_until<F: Fn(&Event) -> bool>(sim: &mut Simulation, ticks: usize, f: F) -> bool {
+        for _ in 0..ticks {
+            sim.tick(Input::default());
+            if sim.events().any(|event| f(&event)) {
+                return true
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn bullet_kills_enemy() {
+        let mut world = world();
+        world.enemies.push(enemy_at(Point2::new(356., 256.), 1.));
+        let mut sim = Simulation::new(world, Difficulty::Normal);
+
+        sim.shoot();
+        assert!(sim.events().any(|event| matches!(event, Event::Shot(_))));
+        assert!(tick_until(&mut sim, 60, |event| matches!(event, Event::EnemyDied(_))));
+        assert!(sim.world.enemies.is_empty());
+        assert_eq!(sim.world.weapons.len(), 0, "the enemy had no weapon to drop");
+    }
+
+    #[test]
+    fn explosion_kills_enemy() {
+        let mut world = world();
+        let pos = Point2::new(356., 256.);
+        world.enemies.push(enemy_at(pos, 1.));
+        world.enemies[0].pl.wep = Some(WEAPONS["glock"].make_instance());
+        world.grenades.push(Grenade {
+            obj: Object::new(pos - Vector2::new(20., 0.)),
+            vel: Vector2::new(0., 0.),
+            fuse: 0.,
+            grenade_type: &GRENADES["frag"],
+        });
+        let mut sim = Simulation::new(world, Difficulty::Normal);
+
+        assert!(tick_until(&mut sim, 1, |event| matches!(event, Event::EnemyDied(_))));
+        assert!(sim.world.enemies.is_empty());
+        assert_eq!(sim.world.weapons.len(), 1, "the enemy's weapon is left behind");
+    }
+
+    #[test]
+    fn player_dies() {
+        let mut world = world();
+        world.player.health.hp = 1.;
+        world.player.health.armour = 0.;
+        world.bullets.push(Bullet::new(Object::with_rot(Point2::new(156., 256.), 0.), &WEAPONS["glock"]));
+        let mut sim = Simulation::new(world, Difficulty::Normal);
+
+        assert!(tick_until(&mut sim, 60, |event| matches!(event, Event::PlayerDied)));
+        assert!(sim.world.player.health.is_dead());
+    }
+
+    #[test]
+    fn weapons_give_their_effects() {
+        let mut world = world();
+        world.player.health.hp = 1000.;
+        world.bullets.push(Bullet::new(Object::with_rot(Point2::new(156., 256.), 0.), &WEAPONS["magnum"]));
+        let mut sim = Simulation::new(world, Difficulty::Normal);
+
+        for _ in 0..10 {
+            sim.tick(Input::default());
+        }
+        assert!(sim.world.bullets.is_empty());
+        assert!(sim.world.player.effects.get(EffectKind::Bleeding).is_some());
+    }
+
+    #[test]
+    fn intel_picked_up() {
+        let mut world = world();
+        world.intels.push(Point2::new(260., 256.));
+        world.intels.push(Point2::new(400., 400.));
+        let mut sim = Simulation::new(world, Difficulty::Normal);
+
+        sim.tick(Input::default());
+        assert!(sim.events().any(|event| matches!(event, Event::Sound("hit"))));
+        assert_eq!(sim.world.intels, vec![Point2::new(400., 400.)]);
+    }
+
+    #[test]
+    fn exit_reached() {
+        let mut world = world();
+        world.exit = Some(Point2::new(400., 256.));
+        let mut sim = Simulation::new(world, Difficulty::Normal);
+
+        assert!(!tick_until(&mut sim, 30, |event| matches!(event, Event::Won)));
+        let input = Input {
+            hor: 1.,
+            .. Input::default()
+        };
+        for _ in 0..120 {
+            sim.tick(input);
+        }
+        assert!(sim.events().any(|event| matches!(event, Event::Sound("victory"))));
+        assert!(tick_until(&mut sim, 150, |event| matches!(event, Event::Won)));
+    }
+
+    #[test]
+    fn same_seed_plays_out_the_same() {
+        let shots = |seed| {
+            let mut world = world();
+            let mut enemy = enemy_at(Point2::new(56., 256.), 100.);
+            enemy.pl.wep = Some(WEAPONS["glock"].make_instance());
+            enemy.alert();
+            world.enemies.push(enemy);
+            let mut sim = Simulation::with_seed(world, Difficulty::Normal, seed);
+
+            let mut shots = Vec::new();
+            for _ in 0..180 {
+                sim.tick(Input::default());
+                shots.extend(sim.events().filter_map(|event| match event {
+                    Event::Shot(obj) => Some(obj.rot),
+                    _ => None,
+                }));
+            }
+            shots
+        };
+        let first = shots(7);
+        assert!(!first.is_empty());
+        assert_eq!(first, shots(7));
+        assert_ne!(first, shots(8));
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
         tex::PosText,
         btn::Button,
     },
-    obj::{health::Health, weapon::WeaponInstance},
+    obj::{health::Health, player::Inventory},
 };
 use ggez::{
     Context, GameResult,
@@ -30,12 +30,13 @@ pub struct Win {
     level_complete: PosText,
     hits_text: PosText,
     misses_text: PosText,
+    headshots_text: PosText,
     enemies_text: PosText,
     health_text: PosText,
     buttons: WinButtons,
     health: Health,
     level: Level,
-    weapon: Option<WeaponInstance<'static>>
+    inventory: Inventory,
 }
 
 impl Win {
@@ -46,8 +47,9 @@ impl Win {
         let level_complete = s.assets.text(Point2::new(s.width as f32/ 2., 10.)).and_text("LEVEL COMPLETE");
         let hits_text = s.assets.text(Point2::new(4., 20.)).and_text("Hits: ").and_text(format!("Hits: {}", stats.hits));
         let misses_text = s.assets.text(Point2::new(4., 36.)).and_text(format!("Misses: {}", stats.misses));
-        let enemies_text = s.assets.text(Point2::new(4., 52.)).and_text(format!("Enemies left: {}", stats.enemies_left));
-        let health_text = s.assets.text(Point2::new(4., 68.)).and_text(format!("Health left: {:02.0} / {:02.0}", stats.health_left.hp, stats.health_left.armour));
+        let headshots_text = s.assets.text(Point2::new(4., 52.)).and_text(format!("Headshots: {}", stats.headshots));
+        let enemies_text = s.assets.text(Point2::new(4., 68.)).and_text(format!("Enemies left: {}", stats.enemies_left));
+        let health_text = s.assets.text(Point2::new(4., 84.)).and_text(format!("Health left: {:02.0} / {:02.0}", stats.health_left.hp, stats.health_left.armour));
 
         Ok(Box::new(Win {
             buttons: {
@@ -64,11 +66,12 @@ impl Win {
             level_complete,
             hits_text,
             misses_text,
+            headshots_text,
             enemies_text,
             health_text,
             level: stats.level,
             health: stats.health_left,
-            weapon: stats.weapon,
+            inventory: stats.inventory,
         }))
     }
     fn restart(&self, s: &mut State) {
@@ -90,7 +93,7 @@ impl Win {
             Content::None | Content::File(_) => return,
         }
 
-        s.switch(StateSwitch::PlayWith{health: self.health, wep: self.weapon, lvl: Box::new(lvl)});
+        s.switch(StateSwitch::PlayWith{health: self.health, inventory: self.inventory.clone(), lvl: Box::new(lvl)});
     }
 }
 
@@ -109,6 +112,7 @@ impl GameState for Win {
         self.level_complete.draw_center(ctx)?;
         self.hits_text.draw_text(ctx)?;
         self.misses_text.draw_text(ctx)?;
+        self.headshots_text.draw_text(ctx)?;
         self.enemies_text.draw_text(ctx)?;
         self.health_text.draw_text(ctx)
     }
@@ -4,29 +4,31 @@ use crate::{
     io::save::Point2Def,
     obj::{
         Object,
-        player::Player,
+        player::{Player, Inventory},
         enemy::Enemy,
         health::Health,
         bullet::Bullet,
-        grenade::Grenade,
-        weapon::{WeaponInstance, WeaponDrop, WEAPONS},
-        pickup::Pickup,
-        decoration::{Decoration, OldDecoration},
+        grenade::{Grenade, Area},
+        weapon::{WeaponInstance, WeaponDrop},
+        pickup::{Pickup, PickupType},
+        decoration::Decoration,
     }
 };
-use ggez::{
-    Context, GameResult,
-    error::GameError,
-};
+use ggez::{Context, GameResult};
 
 use std::path::Path;
 use std::fs::File;
-use std::io::{Write, BufRead, BufReader};
-
-use bincode;
+use std::io::BufReader;
 
 mod material;
 pub use material::*;
+mod text;
+pub use self::text::{TEXT_EXTENSION, is_text_level, weapon_id};
+mod binary;
+pub use self::binary::{LoadError, VERSION};
+mod path;
+mod noise;
+pub use self::noise::{Noise, WALL_DAMPING};
 
 #[derive(Debug)]
 /// All the objects in the current world
@@ -39,6 +41,8 @@ pub struct World {
     pub enemies: Vec<Enemy>,
     pub bullets: Vec<Bullet<'static>>,
     pub grenades: Vec<Grenade>,
+    /// Smoke clouds and fires left by grenades
+    pub areas: Vec<Area>,
     pub weapons: Vec<WeaponDrop<'static>>,
     pub decorations: Vec<Decoration>,
     pub pickups: Vec<Pickup>,
@@ -66,11 +70,12 @@ impl World {
             }
             for i in deads.into_iter() {
                 let pickup = self.pickups.remove(i);
-                let _action_done = pickup.apply(&mut enemy.pl.health);
+                let _action_done = pickup.apply(&mut enemy.pl, 1.);
             }
         }
     }
-    pub fn player_pickup(&mut self) {
+    /// Picks up what the player starts on, pickups have their effect scaled by `effectiveness`
+    pub fn player_pickup(&mut self, effectiveness: f32) {
         let player = &mut self.player;
         if player.wep.is_none() {
             let mut dead = None;
@@ -81,7 +86,9 @@ impl World {
                 }
             }
             if let Some(i) = dead {
-                player.wep = Some(WeaponInstance::from_drop(self.weapons.remove(i)));
+                if let Some(old) = player.pick_up(self.weapons.remove(i)) {
+                    self.weapons.push(old);
+                }
             }
         }
 
@@ -94,7 +101,7 @@ impl World {
         }
         for i in deads.into_iter() {
             let pickup = self.pickups.remove(i);
-            let _action_done = pickup.apply(&mut player.health);
+            let _action_done = pickup.apply(player, effectiveness);
         }
     }
 }
@@ -102,10 +109,13 @@ impl World {
 pub struct Statistics {
     pub hits: usize,
     pub misses: usize,
+    /// Enemies shot in the head
+    pub headshots: usize,
     pub enemies_left: usize,
     pub health_left: Health,
     pub level: Level,
-    pub weapon: Option<WeaponInstance<'static>>,
+    /// What the player has when the level is over, or had when it started if it was lost
+    pub inventory: Inventory,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,7 +164,7 @@ pub struct Level {
     pub enemies: Vec<Enemy>,
     pub exit: Option<Point2>,
     pub intels: Vec<Point2>,
-    pub pickups: Vec<(Point2, u8)>,
+    pub pickups: Vec<(Point2, &'static PickupType)>,
     pub decorations: Vec<Decoration>,
     pub weapons: Vec<WeaponDrop<'static>>,
 }
@@ -173,121 +183,27 @@ impl Level {
             weapons: Vec::new(),
         }
     }
+    /// Loads a level, as text if it has the `TEXT_EXTENSION` and as binary otherwise
     pub fn load<P: AsRef<Path>>(path: P) -> GameResult<Self> {
-        let mut reader = BufReader::new(File::open(path)?);
-        let mut ret = Level::new(Palette::default(), 0, 0);
-
-        // For support of older level files
-        const WEAPONS_OLD: [&str; 6] = [
-            "glock",
-            "five_seven",
-            "magnum",
-            "m4a1",
-            "ak47",
-            "arwp",
-        ];
-
-        loop {
-            let mut buf = String::with_capacity(16);
-            reader.read_line(&mut buf)?;
-            match &*buf.trim_end() {
-                "" => continue,
-                "PALETTE" => ret.palette = bincode::deserialize_from(&mut reader)
-                    .map(|mats: Vec<Box<str>>| Palette::new(mats.into_iter().map(|s| &*Box::leak(s)).collect()))
-                    .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?,
-                "GRD" => ret.grid = bincode::deserialize_from(&mut reader)
-                    .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?,
-                "GRID" => {
-                    let (w, grid): (usize, Vec<u16>) = bincode::deserialize_from(&mut reader)
-                    .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?;
-                    ret.grid = Grid {
-                        mats: grid.into_iter().map(|n| n as u8).collect(),
-                        width: w as u16
-                    }
-                }
-                "START" => ret.start_point = Some(
-                    bincode::deserialize_from(&mut reader)
-                        .map(|(x, y)| Point2::new(x, y))
-                        .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?
-                ),
-                "ENEMIES" => ret.enemies = bincode::deserialize_from(&mut reader)
-                    .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?,
-                "POINT GOAL" => ret.exit = Some(bincode::deserialize_from(&mut reader)
-                    .map(|(x, y)| Point2::new(x, y))
-                    .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?),
-                "INTELS" => ret.intels = bincode::deserialize_from(&mut reader)
-                    .map(|l: Vec<(f32, f32)>| l.into_iter().map(|(x, y)| Point2::new(x, y)).collect())
-                    .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?,
-                "DECORATIONS" => ret.decorations = bincode::deserialize_from(&mut reader)
-                    .map(|old_decs: Vec<OldDecoration>| old_decs.into_iter().map(|od| od.renew()).collect())
-                    .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?,
-                "DECS" => ret.decorations = bincode::deserialize_from(&mut reader)
-                    .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?,
-                "PICKUPS" => ret.pickups = bincode::deserialize_from(&mut reader)
-                    .map(|l: Vec<((f32, f32), u8)>| l.into_iter().map(|((x, y), i)| (Point2::new(x, y), i)).collect())
-                    .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?,
-                "WEPS" => ret.weapons = bincode::deserialize_from(&mut reader)
-                    .map(|l: Vec<((f32, f32), String)>| l.into_iter().map(|((x, y), id)| WEAPONS[&id].make_drop(Point2::new(x, y))).collect())
-                    .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?,
-                "WEAPONS" => ret.weapons = bincode::deserialize_from(&mut reader)
-                    .map(|l: Vec<((f32, f32), u8)>| l.into_iter().map(|((x, y), i)| WEAPONS[WEAPONS_OLD[i as usize]].make_drop(Point2::new(x, y))).collect())
-                    .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?,
-                "END" => break, 
-                _ => return Err(GameError::ResourceLoadError("Bad section".to_string()))
-            }
+        let path = path.as_ref();
+        if is_text_level(path) {
+            text::load(path)
+        } else {
+            Ok(binary::read(BufReader::new(File::open(path)?))?)
         }
-
-        Ok(ret)
     }
+    /// Saves the level, as text if it has the `TEXT_EXTENSION` and as binary otherwise
     pub fn save<P: AsRef<Path>>(&self, path: P) -> GameResult<()> {
-        let mut file = File::create(path)?;
-
-        writeln!(file, "GRD")?;
-        bincode::serialize_into(&mut file, &self.grid)
-            .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?;
-        if let Some(start) = self.start_point {
-            writeln!(file, "\nSTART")?;
-            bincode::serialize_into(&mut file, &(start.x, start.y))
-            .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?;
-        }
-        if !self.enemies.is_empty() {
-            writeln!(file, "\nENEMIES")?;
-            bincode::serialize_into(&mut file, &self.enemies)
-            .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?;
-        }
-        if let Some(p) = self.exit {
-            writeln!(file, "\nPOINT GOAL")?;
-            bincode::serialize_into(&mut file, &(p.x, p.y))
-            .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?;
-        }
-        if !self.intels.is_empty() {
-            writeln!(file, "\nINTELS")?;
-            let intels: Vec<_> = self.intels.iter().map(|p| (p.x, p.y)).collect();
-            bincode::serialize_into(&mut file, &intels)
-                .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?;
-        }
-        if !self.decorations.is_empty() {
-            writeln!(file, "\nDECS")?;
-            bincode::serialize_into(&mut file, &self.decorations)
-            .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?;
-        }
-        if !self.pickups.is_empty() {
-            writeln!(file, "\nPICKUPS")?;
-            let pickups: Vec<_> = self.pickups.iter().map(|&(p, i)| ((p.x, p.y), i)).collect();
-            bincode::serialize_into(&mut file, &pickups)
-                .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?;
-        }
-        if !self.weapons.is_empty() {
-            writeln!(file, "\nWEPS")?;
-            let pickups: Vec<((f32, f32), String)> = self.weapons.iter().map(|w| ((w.pos.x, w.pos.y), {
-                WEAPONS.iter().find(|(_, wep)| wep.name == w.weapon.name).map(|(id, _)| id.clone()).unwrap()
-            })).collect();
-            bincode::serialize_into(&mut file, &pickups)
-                .map_err(|e| GameError::ResourceLoadError(format!("{:?}", e)))?;
+        let path = path.as_ref();
+        if is_text_level(path) {
+            text::save(self, path)
+        } else {
+            binary::write(self, File::create(path)?)
         }
-
-        writeln!(file, "\nEND")?;
-        Ok(())
+    }
+    /// Converts a level file between the binary and text formats (decided by the extensions)
+    pub fn convert<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> GameResult<()> {
+        Level::load(from)?.save(to)
     }
 }
 
@@ -384,6 +300,58 @@ impl Grid {
     pub fn is_solid(&self, pal: &Palette, x: u16, y: u16) -> bool {
         self.get(x, y).map(|m| pal.is_solid(m)).unwrap_or(true)
     }
+    /// Restitution of the material at the point, off the grid is like the default material
+    pub fn restitution(&self, pal: &Palette, p: Point2) -> f32 {
+        let (x, y) = Self::snap(p);
+        self.get(x, y).map(|m| pal.restitution(m)).unwrap_or_else(|| restitution("missing"))
+    }
+    /// Material at the point, off the grid is the default material
+    pub fn material(&self, pal: &Palette, p: Point2) -> &'static str {
+        let (x, y) = Self::snap(p);
+        self.get(x, y).and_then(|m| pal.get(m)).unwrap_or("missing")
+    }
+    /// Where a line from a point inside a tile leaves it
+    pub fn tile_exit(p: Point2, dir: Vector2) -> Point2 {
+        let (x, y) = Self::snap(p);
+        let exit = |pos: f32, tile: u16, d: f32| {
+            let edge = f32::from(tile) * 32. + if d > 0. { 32. } else { 0. };
+            if d == 0. { std::f32::INFINITY } else { (edge - pos) / d }
+        };
+        let time = exit(p.x, x, dir.x).min(exit(p.y, y, dir.y));
+        p + time * dir
+    }
+    /// Finds every tile that can be walked to from the given tile without crossing solid tiles
+    pub fn walkable_from(&self, pal: &Palette, (x, y): (u16, u16)) -> Walkable {
+        let mut tiles = vec![false; self.mats.len()];
+
+        if !self.is_solid(pal, x, y) {
+            tiles[self.idx(x, y)] = true;
+            let mut stack = vec![(x, y)];
+
+            while let Some((x, y)) = stack.pop() {
+                let neighbours = [
+                    x.checked_sub(1).map(|x| (x, y)),
+                    y.checked_sub(1).map(|y| (x, y)),
+                    Some((x + 1, y)),
+                    Some((x, y + 1)),
+                ];
+                for &(nx, ny) in neighbours.iter().flatten() {
+                    if !self.is_solid(pal, nx, ny) {
+                        let i = self.idx(nx, ny);
+                        if !tiles[i] {
+                            tiles[i] = true;
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+            }
+        }
+
+        Walkable {
+            width: self.width,
+            tiles,
+        }
+    }
     pub fn insert(&mut self, x: u16, y: u16, mat: u8) {
         if x < self.width {
             let i = self.idx(x, y);
@@ -502,6 +470,19 @@ impl Grid {
     }
 }
 
+#[derive(Debug, Clone)]
+/// The tiles of a grid that are connected to some tile
+pub struct Walkable {
+    width: u16,
+    tiles: Vec<bool>,
+}
+
+impl Walkable {
+    pub fn contains(&self, (x, y): (u16, u16)) -> bool {
+        x < self.width && self.tiles.get(x as usize + y as usize * self.width as usize).cloned().unwrap_or(false)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 enum Direction {
     Pos,
@@ -0,0 +1,617 @@
+//! The binary `.lvl` format
+//!
+//! A level file is a series of sections, each a line with the section's name followed by its bincode data,
+//! and it ends with an `END` line. Since version 1 the file starts with a `VERSION` line.
+//! Files without one are version 0.
+//!
+//! Loading reads the sections as they were written in the file's version
+//! and then runs them through `MIGRATIONS` one version at a time until they are up to date.
+use crate::{
+    util::Point2,
+    obj::{
+        Object,
+        enemy::{Enemy, Patrol, TACTICS, DEFAULT_TACTICS, ARCHETYPES, DEFAULT_ARCHETYPE},
+        health::Health,
+        decoration::{Decoration, OldDecoration},
+        weapon::{WeaponDrop, WeaponInstance, WEAPONS},
+        pickup::PICKUPS,
+    },
+};
+use ggez::error::GameError;
+use serde::de::DeserializeOwned;
+
+use std::fmt::{self, Display};
+use std::error::Error;
+use std::io::{self, Read, BufRead, Write};
+
+use super::{Level, Grid, Palette, weapon_id};
+
+/// The version written by `write`
+pub const VERSION: u32 = 5;
+
+const VERSION_TAG: &str = "VERSION";
+
+/// Upgrades the sections of a level from the version of its index to the next
+type Migration = fn(Vec<Located>) -> Result<Vec<Located>, LoadError>;
+
+const MIGRATIONS: [Migration; VERSION as usize] = [
+    v0_to_v1,
+    v1_to_v2,
+    v2_to_v3,
+    v3_to_v4,
+    v4_to_v5,
+];
+
+#[derive(Debug)]
+/// Why a level couldn't be loaded
+pub enum LoadError {
+    Io(io::Error),
+    /// The `VERSION` line couldn't be read
+    BadHeader {
+        offset: u64,
+    },
+    /// The file is from a newer version of the game
+    UnsupportedVersion(u32),
+    UnknownSection {
+        section: String,
+        offset: u64,
+    },
+    /// The data of a section is corrupt or refers to something that doesn't exist
+    BadSection {
+        section: String,
+        offset: u64,
+        error: String,
+    },
+    /// The file ended before the `END` line
+    MissingEnd,
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::BadHeader{offset} => write!(f, "bad version header at byte {}", offset),
+            LoadError::UnsupportedVersion(v) => write!(f, "level is version {} but only up to version {} is supported", v, VERSION),
+            LoadError::UnknownSection{section, offset} => write!(f, "unknown section {:?} at byte {}", section, offset),
+            LoadError::BadSection{section, offset, error} => write!(f, "bad section {} at byte {}: {}", section, offset, error),
+            LoadError::MissingEnd => write!(f, "file ended without an END section"),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    #[inline]
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+impl From<LoadError> for GameError {
+    #[inline]
+    fn from(e: LoadError) -> Self {
+        GameError::ResourceLoadError(e.to_string())
+    }
+}
+
+/// A weapon's id with its current clip and remaining ammo
+type SavedWeapon = (String, u16, u16);
+
+/// The data of a section as it was written in some version
+#[derive(Debug)]
+enum Section {
+    Palette(Vec<Box<str>>),
+    Grid(Grid),
+    Start(Point2),
+    Exit(Point2),
+    Intels(Vec<Point2>),
+    Enemies(Vec<(Object, Health, Option<SavedWeapon>)>),
+    Decorations(Vec<Decoration>),
+    /// Ids in `pickups.toml` of the pickups
+    Pickups(Vec<(Point2, String)>),
+    Weapons(Vec<(Point2, SavedWeapon)>),
+    /// Patrols of the enemies at the indices in `ENEMIES`
+    Patrols(Vec<(u32, Patrol)>),
+    /// Ids in `tactics.toml` of the enemies at the indices in `ENEMIES`
+    Tactics(Vec<(u32, String)>),
+    /// Ids in `archetypes.toml` of the enemies at the indices in `ENEMIES`
+    Archetypes(Vec<(u32, String)>),
+    /// Version 0 `GRID` with 16-bit materials
+    WideGrid(usize, Vec<u16>),
+    /// Version 0 `ENEMIES` without health
+    PlainEnemies(Vec<Object>),
+    /// Version 0 `DECORATIONS` referring to sprites by index
+    OldDecorations(Vec<OldDecoration>),
+    /// Version 0 `WEAPONS` referring to weapons by index
+    IndexedWeapons(Vec<(Point2, u8)>),
+    /// Version 0 `WEPS` without ammo
+    NamedWeapons(Vec<(Point2, String)>),
+    /// Version 4 `PICKUPS` referring to pickups by index
+    IndexedPickups(Vec<(Point2, u8)>),
+}
+
+#[derive(Debug)]
+/// A section and where it was in the file
+struct Located {
+    tag: String,
+    offset: u64,
+    section: Section,
+}
+
+impl Located {
+    fn error<E: Display>(&self, error: E) -> LoadError {
+        LoadError::BadSection {
+            section: self.tag.clone(),
+            offset: self.offset,
+            error: error.to_string(),
+        }
+    }
+}
+
+/// Keeps track of how many bytes have been read
+struct Counter<R> {
+    inner: R,
+    pos: u64,
+}
+
+impl<R: Read> Read for Counter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Counter<R> {
+    #[inline]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt as u64;
+        self.inner.consume(amt)
+    }
+}
+
+#[inline]
+fn point((x, y): (f32, f32)) -> Point2 {
+    Point2::new(x, y)
+}
+
+fn des<T: DeserializeOwned, R: Read>(reader: R) -> bincode::Result<T> {
+    bincode::deserialize_from(reader)
+}
+
+/// Reads the data of a section as written in `version`, `None` if there's no such section
+fn read_section<R: Read>(version: u32, tag: &str, mut r: R) -> bincode::Result<Option<Section>> {
+    let r = &mut r;
+    Ok(Some(match (version, tag) {
+        (_, "PALETTE") => Section::Palette(des(r)?),
+        (_, "GRD") => Section::Grid(des(r)?),
+        (_, "START") => Section::Start(point(des(r)?)),
+        (_, "POINT GOAL") => Section::Exit(point(des(r)?)),
+        (_, "INTELS") => Section::Intels(des::<Vec<(f32, f32)>, _>(r)?.into_iter().map(point).collect()),
+        (_, "DECS") => Section::Decorations(des(r)?),
+        (0, "GRID") => {
+            let (w, grid) = des(r)?;
+            Section::WideGrid(w, grid)
+        }
+        (0, "ENEMIES") => Section::PlainEnemies(des(r)?),
+        (0, "DECORATIONS") => Section::OldDecorations(des(r)?),
+        (0, "WEAPONS") => Section::IndexedWeapons(des::<Vec<((f32, f32), u8)>, _>(r)?.into_iter().map(|(p, i)| (point(p), i)).collect()),
+        (0, "WEPS") => Section::NamedWeapons(des::<Vec<((f32, f32), String)>, _>(r)?.into_iter().map(|(p, id)| (point(p), id)).collect()),
+        (_, "ENEMIES") => Section::Enemies(des(r)?),
+        (_, "WEPS") => Section::Weapons(des::<Vec<((f32, f32), SavedWeapon)>, _>(r)?.into_iter().map(|(p, w)| (point(p), w)).collect()),
+        (v, "PATROLS") if v >= 2 => Section::Patrols(des(r)?),
+        (v, "TACTICS") if v >= 3 => Section::Tactics(des(r)?),
+        (v, "ARCHETYPES") if v >= 4 => Section::Archetypes(des(r)?),
+        (v, "PICKUPS") if v < 5 => Section::IndexedPickups(des::<Vec<((f32, f32), u8)>, _>(r)?.into_iter().map(|(p, i)| (point(p), i)).collect()),
+        (_, "PICKUPS") => Section::Pickups(des::<Vec<((f32, f32), String)>, _>(r)?.into_iter().map(|(p, id)| (point(p), id)).collect()),
+        _ => return Ok(None),
+    }))
+}
+
+pub fn read<R: BufRead>(reader: R) -> Result<Level, LoadError> {
+    let mut reader = Counter {
+        inner: reader,
+        pos: 0,
+    };
+    let mut version = 0;
+    let mut first = true;
+    let mut sections = Vec::new();
+
+    loop {
+        let offset = reader.pos;
+        let mut buf = String::with_capacity(16);
+        if reader.read_line(&mut buf)? == 0 {
+            return Err(LoadError::MissingEnd);
+        }
+        let tag = buf.trim_end();
+        if tag.is_empty() {
+            continue
+        }
+
+        if first {
+            first = false;
+            if let Some(version_str) = tag.strip_prefix(VERSION_TAG) {
+                version = version_str.trim().parse().map_err(|_| LoadError::BadHeader{offset})?;
+                if version > VERSION {
+                    return Err(LoadError::UnsupportedVersion(version));
+                }
+                continue
+            }
+        }
+        if tag == "END" {
+            break
+        }
+
+        let data_offset = reader.pos;
+        match read_section(version, tag, &mut reader) {
+            Ok(Some(section)) => sections.push(Located {
+                tag: tag.to_owned(),
+                offset: data_offset,
+                section,
+            }),
+            Ok(None) => return Err(LoadError::UnknownSection{section: tag.to_owned(), offset}),
+            Err(e) => return Err(LoadError::BadSection{section: tag.to_owned(), offset: data_offset, error: e.to_string()}),
+        }
+    }
+
+    for migrate in &MIGRATIONS[version as usize..] {
+        sections = migrate(sections)?;
+    }
+
+    assemble(sections)
+}
+
+/// Builds the level from up to date sections
+fn assemble(sections: Vec<Located>) -> Result<Level, LoadError> {
+    let mut ret = Level::new(Palette::default(), 0, 0);
+
+    for located in &sections {
+        let get_weapon = |(id, cur_clip, ammo): SavedWeapon, pos| {
+            WEAPONS.get(&id)
+                .map(|weapon| WeaponDrop{pos, cur_clip, ammo, weapon})
+                .ok_or_else(|| located.error(format!("unknown weapon {:?}", id)))
+        };
+
+        match &located.section {
+            Section::Palette(mats) => ret.palette = Palette::new(mats.iter().map(|s| &*Box::leak(s.clone())).collect()),
+            Section::Grid(grid) => ret.grid = grid.clone(),
+            Section::Start(p) => ret.start_point = Some(*p),
+            Section::Exit(p) => ret.exit = Some(*p),
+            Section::Intels(intels) => ret.intels = intels.clone(),
+            Section::Enemies(enemies) => for (obj, health, wep) in enemies {
+                let mut enemy = Enemy::new(obj.clone());
+                enemy.pl.health = *health;
+                if let Some(wep) = wep {
+                    enemy.pl.wep = Some(WeaponInstance::from_drop(get_weapon(wep.clone(), obj.pos)?));
+                }
+                ret.enemies.push(enemy);
+            }
+            Section::Decorations(decs) => ret.decorations = decs.clone(),
+            Section::Pickups(pickups) => for (pos, id) in pickups {
+                let pickup = PICKUPS.get(id).ok_or_else(|| located.error(format!("unknown pickup {:?}", id)))?;
+                ret.pickups.push((*pos, pickup));
+            }
+            Section::Weapons(weapons) => for (pos, wep) in weapons {
+                ret.weapons.push(get_weapon(wep.clone(), *pos)?);
+            }
+            // Added below once all the enemies are there
+            Section::Patrols(_) | Section::Tactics(_) | Section::Archetypes(_) => (),
+            Section::WideGrid(..) |
+            Section::PlainEnemies(_) |
+            Section::OldDecorations(_) |
+            Section::IndexedWeapons(_) |
+            Section::NamedWeapons(_) |
+            Section::IndexedPickups(_) => return Err(located.error("section was not migrated")),
+        }
+    }
+    for located in &sections {
+        match &located.section {
+            Section::Patrols(patrols) => for (i, patrol) in patrols {
+                let enemy = ret.enemies.get_mut(*i as usize)
+                    .ok_or_else(|| located.error(format!("patrol for enemy {} which doesn't exist", i)))?;
+                enemy.patrol = patrol.clone();
+            }
+            Section::Tactics(tactics) => for (i, id) in tactics {
+                let enemy = ret.enemies.get_mut(*i as usize)
+                    .ok_or_else(|| located.error(format!("tactics for enemy {} which doesn't exist", i)))?;
+                enemy.tactics = TACTICS.get(id).ok_or_else(|| located.error(format!("unknown tactics {:?}", id)))?;
+            }
+            Section::Archetypes(archetypes) => for (i, id) in archetypes {
+                let enemy = ret.enemies.get_mut(*i as usize)
+                    .ok_or_else(|| located.error(format!("archetype for enemy {} which doesn't exist", i)))?;
+                enemy.archetype = ARCHETYPES.get(id).ok_or_else(|| located.error(format!("unknown archetype {:?}", id)))?;
+            }
+            _ => (),
+        }
+    }
+
+    Ok(ret)
+}
+
+fn v0_to_v1(sections: Vec<Located>) -> Result<Vec<Located>, LoadError> {
+    // The weapons that `WEAPONS` referred to by index
+    const WEAPONS_OLD: [&str; 6] = [
+        "glock",
+        "five_seven",
+        "magnum",
+        "m4a1",
+        "ak47",
+        "arwp",
+    ];
+    /// Version 0 always had weapons fully loaded
+    fn full_weapon(located: &Located, id: &str) -> Result<SavedWeapon, LoadError> {
+        let weapon = WEAPONS.get(id).ok_or_else(|| located.error(format!("unknown weapon {:?}", id)))?;
+        let cur_clip = weapon.clip_size.get();
+        Ok((id.to_owned(), cur_clip, cur_clip * weapon.clips.get()))
+    }
+
+    sections.into_iter().map(|located| {
+        let section = match located.section {
+            Section::WideGrid(w, ref grid) => Section::Grid(Grid {
+                mats: grid.iter().map(|&n| n as u8).collect(),
+                width: w as u16
+            }),
+            Section::PlainEnemies(ref objs) => Section::Enemies(objs.iter().map(|obj| (obj.clone(), Health::default(), None)).collect()),
+            Section::OldDecorations(ref decs) => Section::Decorations(decs.iter().cloned().map(OldDecoration::renew).collect()),
+            Section::IndexedWeapons(ref weapons) => {
+                let mut new = Vec::with_capacity(weapons.len());
+                for &(p, i) in weapons {
+                    let id = WEAPONS_OLD.get(i as usize).ok_or_else(|| located.error(format!("unknown weapon index {}", i)))?;
+                    new.push((p, full_weapon(&located, id)?));
+                }
+                Section::Weapons(new)
+            }
+            Section::NamedWeapons(ref weapons) => {
+                let mut new = Vec::with_capacity(weapons.len());
+                for (p, id) in weapons {
+                    new.push((*p, full_weapon(&located, id)?));
+                }
+                Section::Weapons(new)
+            }
+            _ => return Ok(located),
+        };
+        Ok(Located {
+            section,
+            .. located
+        })
+    }).collect()
+}
+
+/// Version 2 only added the `PATROLS` section
+fn v1_to_v2(sections: Vec<Located>) -> Result<Vec<Located>, LoadError> {
+    Ok(sections)
+}
+
+/// Version 3 only added the `TACTICS` section
+fn v2_to_v3(sections: Vec<Located>) -> Result<Vec<Located>, LoadError> {
+    Ok(sections)
+}
+
+/// Version 4 only added the `ARCHETYPES` section
+fn v3_to_v4(sections: Vec<Located>) -> Result<Vec<Located>, LoadError> {
+    Ok(sections)
+}
+
+fn v4_to_v5(sections: Vec<Located>) -> Result<Vec<Located>, LoadError> {
+    // The pickups that `PICKUPS` referred to by index
+    const PICKUPS_OLD: [&str; 5] = [
+        "health_pack",
+        "armour",
+        "adrenaline",
+        "super_armour",
+        "ammo_box",
+    ];
+
+    sections.into_iter().map(|located| {
+        let section = match located.section {
+            Section::IndexedPickups(ref pickups) => {
+                let mut new = Vec::with_capacity(pickups.len());
+                for &(p, i) in pickups {
+                    let id = PICKUPS_OLD.get(i as usize).ok_or_else(|| located.error(format!("unknown pickup index {}", i)))?;
+                    new.push((p, (*id).to_owned()));
+                }
+                Section::Pickups(new)
+            }
+            _ => return Ok(located),
+        };
+        Ok(Located {
+            section,
+            .. located
+        })
+    }).collect()
+}
+
+fn ser<T: serde::Serialize, W: Write>(mut w: W, tag: &str, data: &T) -> io::Result<()> {
+    writeln!(w, "{}", tag)?;
+    bincode::serialize_into(&mut w, data)
+        .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+    writeln!(w)
+}
+
+pub fn write<W: Write>(level: &Level, mut w: W) -> Result<(), GameError> {
+    let w = &mut w;
+    writeln!(w, "{} {}", VERSION_TAG, VERSION)?;
+
+    let palette: Vec<&str> = (0..level.palette.len() as u8).filter_map(|i| level.palette.get(i)).collect();
+    ser(&mut *w, "PALETTE", &palette)?;
+    ser(&mut *w, "GRD", &level.grid)?;
+    if let Some(start) = level.start_point {
+        ser(&mut *w, "START", &(start.x, start.y))?;
+    }
+    if !level.enemies.is_empty() {
+        let mut enemies = Vec::with_capacity(level.enemies.len());
+        for enemy in &level.enemies {
+            let wep = match enemy.pl.wep {
+                Some(wep) => Some((weapon_id(wep.weapon)?, wep.cur_clip, wep.ammo)),
+                None => None,
+            };
+            enemies.push((&enemy.pl.obj, enemy.pl.health, wep));
+        }
+        ser(&mut *w, "ENEMIES", &enemies)?;
+
+        let patrols: Vec<_> = level.enemies.iter()
+            .enumerate()
+            .filter(|(_, enemy)| !enemy.patrol.is_empty())
+            .map(|(i, enemy)| (i as u32, &enemy.patrol))
+            .collect();
+        if !patrols.is_empty() {
+            ser(&mut *w, "PATROLS", &patrols)?;
+        }
+        let tactics: Vec<_> = level.enemies.iter()
+            .enumerate()
+            .filter(|(_, enemy)| &*enemy.tactics.id != DEFAULT_TACTICS)
+            .map(|(i, enemy)| (i as u32, &*enemy.tactics.id))
+            .collect();
+        if !tactics.is_empty() {
+            ser(&mut *w, "TACTICS", &tactics)?;
+        }
+        let archetypes: Vec<_> = level.enemies.iter()
+            .enumerate()
+            .filter(|(_, enemy)| &*enemy.archetype.id != DEFAULT_ARCHETYPE)
+            .map(|(i, enemy)| (i as u32, &*enemy.archetype.id))
+            .collect();
+        if !archetypes.is_empty() {
+            ser(&mut *w, "ARCHETYPES", &archetypes)?;
+        }
+    }
+    if let Some(p) = level.exit {
+        ser(&mut *w, "POINT GOAL", &(p.x, p.y))?;
+    }
+    if !level.intels.is_empty() {
+        let intels: Vec<_> = level.intels.iter().map(|p| (p.x, p.y)).collect();
+        ser(&mut *w, "INTELS", &intels)?;
+    }
+    if !level.decorations.is_empty() {
+        ser(&mut *w, "DECS", &level.decorations)?;
+    }
+    if !level.pickups.is_empty() {
+        let pickups: Vec<_> = level.pickups.iter().map(|&(p, pickup)| ((p.x, p.y), &*pickup.id)).collect();
+        ser(&mut *w, "PICKUPS", &pickups)?;
+    }
+    if !level.weapons.is_empty() {
+        let mut weapons = Vec::with_capacity(level.weapons.len());
+        for wep in &level.weapons {
+            weapons.push(((wep.pos.x, wep.pos.y), (weapon_id(wep.weapon)?, wep.cur_clip, wep.ammo)));
+        }
+        ser(&mut *w, "WEPS", &weapons)?;
+    }
+
+    writeln!(w, "END")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section<T: serde::Serialize>(bytes: &mut Vec<u8>, tag: &str, data: &T) {
+        ser(&mut *bytes, tag, data).unwrap();
+    }
+
+    /// A level as the first version of the game wrote it
+    fn v0_level(weapon_index: u8) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        section(&mut bytes, "GRID", &(2usize, vec![0u16, 1, 1, 0]));
+        section(&mut bytes, "ENEMIES", &vec![Object::with_rot(Point2::new(48., 16.), 1.5)]);
+        section(&mut bytes, "WEAPONS", &vec![((16f32, 48f32), weapon_index)]);
+        bytes.extend_from_slice(b"END\n");
+        bytes
+    }
+
+    #[test]
+    fn migrates_v0() {
+        let level = read(&v0_level(3)[..]).unwrap();
+
+        assert_eq!(level.grid.width(), 2);
+        assert_eq!(level.grid.mats, vec![0, 1, 1, 0]);
+        assert_eq!(level.enemies.len(), 1);
+        assert_eq!(level.enemies[0].pl.obj.pos, Point2::new(48., 16.));
+        assert_eq!(level.enemies[0].pl.health.hp, Health::default().hp);
+        assert!(level.enemies[0].pl.wep.is_none());
+
+        assert_eq!(level.weapons.len(), 1);
+        let drop = &level.weapons[0];
+        let m4 = &WEAPONS["m4a1"];
+        assert_eq!(drop.pos, Point2::new(16., 48.));
+        assert_eq!(weapon_id(drop.weapon).unwrap(), "m4a1");
+        assert_eq!(drop.cur_clip, m4.clip_size.get());
+        assert_eq!(drop.ammo, m4.clip_size.get() * m4.clips.get());
+    }
+
+    #[test]
+    fn unknown_v0_weapon_index() {
+        match read(&v0_level(9)[..]) {
+            Err(LoadError::BadSection{section, error, ..}) => {
+                assert_eq!(section, "WEAPONS");
+                assert_eq!(error, "unknown weapon index 9");
+            }
+            other => panic!("expected a bad section, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    /// A level from before pickups were referred to by id
+    fn v4_level(pickup_indices: &[u8]) -> Vec<u8> {
+        let mut bytes = format!("{} 4\n", VERSION_TAG).into_bytes();
+        let pickups: Vec<_> = pickup_indices.iter().enumerate().map(|(x, &i)| ((32. * x as f32, 0f32), i)).collect();
+        section(&mut bytes, "PICKUPS", &pickups);
+        bytes.extend_from_slice(b"END\n");
+        bytes
+    }
+
+    #[test]
+    fn migrates_v4_pickups() {
+        let level = read(&v4_level(&[0, 4, 2])[..]).unwrap();
+
+        let ids: Vec<_> = level.pickups.iter().map(|(_, pickup)| &*pickup.id).collect();
+        assert_eq!(ids, ["health_pack", "ammo_box", "adrenaline"]);
+        assert_eq!(level.pickups[1].0, Point2::new(32., 0.));
+        assert_eq!(level.pickups[1].1.magazines, PICKUPS["ammo_box"].magazines);
+    }
+
+    #[test]
+    fn unknown_v4_pickup_index() {
+        match read(&v4_level(&[5])[..]) {
+            Err(LoadError::BadSection{section, error, ..}) => {
+                assert_eq!(section, "PICKUPS");
+                assert_eq!(error, "unknown pickup index 5");
+            }
+            other => panic!("expected a bad section, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn written_levels_are_read_back() {
+        let level = read(&v0_level(0)[..]).unwrap();
+        let mut bytes = Vec::new();
+        write(&level, &mut bytes).unwrap();
+        assert!(bytes.starts_with(format!("{} {}\n", VERSION_TAG, VERSION).as_bytes()));
+
+        let again = read(&bytes[..]).unwrap();
+        assert_eq!(again.grid.mats, level.grid.mats);
+        assert_eq!(again.enemies.len(), 1);
+        assert_eq!(weapon_id(again.weapons[0].weapon).unwrap(), "glock");
+    }
+
+    #[test]
+    fn newer_version_is_unsupported() {
+        let bytes = format!("{} {}\nEND\n", VERSION_TAG, VERSION + 1);
+        assert!(matches!(read(bytes.as_bytes()), Err(LoadError::UnsupportedVersion(v)) if v == VERSION + 1));
+    }
+
+    #[test]
+    fn bad_header() {
+        assert!(matches!(read(&b"\nVERSION one\nEND\n"[..]), Err(LoadError::BadHeader{offset: 1})));
+    }
+
+    #[test]
+    fn missing_end() {
+        let mut bytes = v0_level(0);
+        bytes.truncate(bytes.len() - 4);
+        assert!(matches!(read(&bytes[..]), Err(LoadError::MissingEnd)));
+    }
+}
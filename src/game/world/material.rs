@@ -13,7 +13,9 @@ use std::cell::Ref;
 #[derive(Debug)]
 pub struct Mat {
     spr: Box<str>,
-    props: MaterialProperties
+    props: MaterialProperties,
+    impact_snd: Option<&'static str>,
+    hole: &'static str,
 }
 
 lazy_static! {
@@ -32,15 +34,59 @@ fn ensure(mat: &str) {
         } else {
             MaterialProperties::default()
         };
-        let mat_data = Mat { spr: format!("materials/{}", mat).into_boxed_str(), props};
+        // Kept for as long as the game runs so the events can refer to them
+        let impact_snd = props.impact_snd.clone().map(|snd| &*Box::leak(snd));
+        let hole = props.hole.clone().map(|hole| &*Box::leak(hole)).unwrap_or("common/hole");
+        let mat_data = Mat { spr: format!("materials/{}", mat).into_boxed_str(), props, impact_snd, hole};
 
         MATS.write().unwrap().insert(mat.to_owned(), mat_data);
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize)]
 struct MaterialProperties {
     solid: bool,
+    /// Fraction of the speed kept by things bouncing off it
+    #[serde(default = "default_restitution")]
+    restitution: f32,
+    /// How much of a bullet's penetration it takes to go through a tile of it,
+    /// and the fraction of the damage the bullet loses doing so
+    ///
+    /// Nothing goes through it at 1 or more.
+    #[serde(default = "default_bullet_resistance")]
+    bullet_resistance: f32,
+    /// Chance of bullets hitting it at a shallow angle bouncing off
+    #[serde(default)]
+    ricochet: f32,
+    /// Sound of bullets hitting it instead of the weapon's impact sound
+    #[serde(default)]
+    impact_snd: Option<Box<str>>,
+    /// Sprite of the holes bullets leave in it
+    #[serde(default)]
+    hole: Option<Box<str>>,
+}
+
+impl Default for MaterialProperties {
+    fn default() -> Self {
+        MaterialProperties {
+            solid: false,
+            restitution: default_restitution(),
+            bullet_resistance: default_bullet_resistance(),
+            ricochet: 0.,
+            impact_snd: None,
+            hole: None,
+        }
+    }
+}
+
+#[inline]
+fn default_restitution() -> f32 {
+    0.5
+}
+
+#[inline]
+fn default_bullet_resistance() -> f32 {
+    1.
 }
 
 #[inline]
@@ -50,6 +96,43 @@ pub fn is_solid(mat: &str) -> bool {
     MATS.read().unwrap()[mat].props.solid
 }
 
+#[inline]
+pub fn restitution(mat: &str) -> f32 {
+    ensure(mat);
+
+    MATS.read().unwrap()[mat].props.restitution
+}
+
+#[inline]
+pub fn bullet_resistance(mat: &str) -> f32 {
+    ensure(mat);
+
+    MATS.read().unwrap()[mat].props.bullet_resistance
+}
+
+#[inline]
+pub fn ricochet_chance(mat: &str) -> f32 {
+    ensure(mat);
+
+    MATS.read().unwrap()[mat].props.ricochet
+}
+
+/// Sound of bullets hitting it if it has its own
+#[inline]
+pub fn impact_snd(mat: &str) -> Option<&'static str> {
+    ensure(mat);
+
+    MATS.read().unwrap()[mat].impact_snd
+}
+
+/// Sprite of the holes bullets leave in it
+#[inline]
+pub fn bullet_hole(mat: &str) -> &'static str {
+    ensure(mat);
+
+    MATS.read().unwrap()[mat].hole
+}
+
 #[inline]
 pub fn get_img<'a>(ctx: &mut Context, assets: &'a Assets, mat: &str) -> Ref<'a, Image> {
     ensure(mat);
@@ -117,8 +200,11 @@ impl Palette {
     pub fn is_solid(&self, i: u8) -> bool {
         is_solid(self.materials[i as usize])
     }
+    pub fn restitution(&self, i: u8) -> f32 {
+        restitution(self.materials[i as usize])
+    }
     #[inline]
-    pub fn get(&self, i: u8) -> Option<&str> {
+    pub fn get(&self, i: u8) -> Option<&'static str> {
         self.materials.get(i as usize).copied()
     }
     #[inline]
@@ -0,0 +1,63 @@
+//! How far sounds carry through the level
+use crate::util::Point2;
+
+use super::{Grid, Palette};
+
+/// How much further away a sound seems for every solid tile it has to go through
+pub const WALL_DAMPING: f32 = 256.;
+
+/// A sound that enemies might hear
+#[derive(Debug, Clone, Copy)]
+pub struct Noise {
+    pub pos: Point2,
+    /// How far away it can be heard when nothing is in the way
+    pub radius: f32,
+}
+
+impl Noise {
+    #[inline]
+    pub fn new(pos: Point2, radius: f32) -> Self {
+        Noise {
+            pos,
+            radius,
+        }
+    }
+    /// How far away the noise sounds from `at`, `None` if it can't be heard there
+    pub fn heard_at(&self, at: Point2, pal: &Palette, grid: &Grid) -> Option<f32> {
+        let distance = (self.pos - at).norm();
+        if distance > self.radius {
+            return None;
+        }
+
+        let muffled = distance + WALL_DAMPING * grid.solid_tiles_between(pal, self.pos, at) as f32;
+        if muffled <= self.radius {
+            Some(muffled)
+        } else {
+            None
+        }
+    }
+}
+
+impl Grid {
+    /// Counts the solid tiles a straight line between two points goes through
+    pub fn solid_tiles_between(&self, pal: &Palette, from: Point2, to: Point2) -> u32 {
+        // Less than a quarter of a tile so no tile is skipped
+        const STEP: f32 = 7.;
+
+        let dist = to - from;
+        let steps = (dist.norm() / STEP).ceil().max(1.) as u32;
+
+        let mut count = 0;
+        let mut last = None;
+        for i in 0..=steps {
+            let tile = Self::snap(from + dist * (i as f32 / steps as f32));
+            if last != Some(tile) {
+                last = Some(tile);
+                if self.is_solid_tuple(pal, tile) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+}
@@ -0,0 +1,189 @@
+//! Pathfinding on the tile grid
+use crate::util::Point2;
+
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::cmp::Reverse;
+
+use super::{Grid, Palette};
+
+const STRAIGHT: u32 = 10;
+const DIAGONAL: u32 = 14;
+
+const NEIGHBOURS: [(i8, i8); 8] = [
+    (1, 0), (-1, 0), (0, 1), (0, -1),
+    (1, 1), (1, -1), (-1, 1), (-1, -1),
+];
+
+#[inline]
+fn offset(n: u16, d: i8) -> Option<u16> {
+    match d {
+        -1 => n.checked_sub(1),
+        1 => n.checked_add(1),
+        _ => Some(n),
+    }
+}
+
+/// Octile distance between two tiles
+fn heuristic((ax, ay): (u16, u16), (bx, by): (u16, u16)) -> u32 {
+    let dx = u32::from(ax.abs_diff(bx));
+    let dy = u32::from(ay.abs_diff(by));
+
+    STRAIGHT * (dx + dy) - (2 * STRAIGHT - DIAGONAL) * dx.min(dy)
+}
+
+impl Grid {
+    /// The centre of a tile in world coordinates
+    #[inline]
+    pub fn tile_centre((x, y): (u16, u16)) -> Point2 {
+        Point2::new(f32::from(x) * 32. + 16., f32::from(y) * 32. + 16.)
+    }
+    /// Finds the shortest walkable path between two points using A*
+    ///
+    /// The path goes through the centres of the tiles on the way and ends in `to`, but doesn't include `from`.
+    /// Diagonal steps are only taken when they don't cut the corner of a solid tile.
+    pub fn find_path(&self, pal: &Palette, from: Point2, to: Point2) -> Option<Vec<Point2>> {
+        let start = Self::snap(from);
+        let goal = Self::snap(to);
+
+        if self.is_solid_tuple(pal, goal) {
+            return None;
+        }
+
+        let mut open = BinaryHeap::new();
+        let mut closed = HashSet::new();
+        let mut came_from = HashMap::new();
+        let mut costs = HashMap::new();
+
+        costs.insert(start, 0);
+        open.push(Reverse((heuristic(start, goal), start)));
+
+        while let Some(Reverse((_, cur))) = open.pop() {
+            if cur == goal {
+                let mut path = vec![to];
+                let mut tile = cur;
+                while let Some(&prev) = came_from.get(&tile) {
+                    if prev == start {
+                        break
+                    }
+                    path.push(Self::tile_centre(prev));
+                    tile = prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if !closed.insert(cur) {
+                continue
+            }
+            let cost = costs[&cur];
+
+            for &(dx, dy) in &NEIGHBOURS {
+                let next = match (offset(cur.0, dx), offset(cur.1, dy)) {
+                    (Some(x), Some(y)) => (x, y),
+                    _ => continue,
+                };
+                if closed.contains(&next) || self.is_solid_tuple(pal, next) {
+                    continue
+                }
+                let step = if dx != 0 && dy != 0 {
+                    if self.is_solid(pal, next.0, cur.1) || self.is_solid(pal, cur.0, next.1) {
+                        continue
+                    }
+                    DIAGONAL
+                } else {
+                    STRAIGHT
+                };
+
+                let new_cost = cost + step;
+                if costs.get(&next).is_none_or(|&c| new_cost < c) {
+                    costs.insert(next, new_cost);
+                    came_from.insert(next, cur);
+                    open.push(Reverse((new_cost + heuristic(next, goal), next)));
+                }
+            }
+        }
+
+        None
+    }
+    /// Finds the centre of the closest walkable tile within `range` of `from` that satisfies `pred`
+    pub fn nearest_tile<F: FnMut(Point2) -> bool>(&self, pal: &Palette, from: Point2, range: f32, mut pred: F) -> Option<Point2> {
+        let start = Self::snap(from);
+
+        let mut queue = VecDeque::new();
+        let mut seen = HashSet::new();
+        queue.push_back(start);
+        seen.insert(start);
+
+        while let Some(tile) = queue.pop_front() {
+            let centre = Self::tile_centre(tile);
+            if pred(centre) {
+                return Some(centre);
+            }
+            for &(dx, dy) in &NEIGHBOURS[..4] {
+                let next = match (offset(tile.0, dx), offset(tile.1, dy)) {
+                    (Some(x), Some(y)) => (x, y),
+                    _ => continue,
+                };
+                if self.is_solid_tuple(pal, next) || (Self::tile_centre(next) - from).norm() > range {
+                    continue
+                }
+                if seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALL: u8 = 1;
+
+    /// An open 8x8 grid with a wall down the middle, leaving a gap in the bottom row
+    fn walled() -> Grid {
+        let mut grid = Grid::new(8, 8);
+        for y in 0..7 {
+            grid.insert(4, y, WALL);
+        }
+        grid
+    }
+
+    #[test]
+    fn goes_around_the_wall() {
+        let grid = walled();
+        let pal = Palette::default();
+        let from = Grid::tile_centre((1, 1));
+        let to = Grid::tile_centre((6, 1));
+
+        let path = grid.find_path(&pal, from, to).unwrap();
+
+        assert_eq!(path.last(), Some(&to));
+        assert!(path.contains(&Grid::tile_centre((4, 7))));
+        for p in &path {
+            assert!(!grid.is_solid_tuple(&pal, Grid::snap(*p)), "path crosses the wall at {:?}", p);
+        }
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let mut grid = walled();
+        grid.insert(4, 7, WALL);
+        let pal = Palette::default();
+
+        assert_eq!(grid.find_path(&pal, Grid::tile_centre((1, 1)), Grid::tile_centre((6, 1))), None);
+        assert_eq!(grid.find_path(&pal, Grid::tile_centre((1, 1)), Grid::tile_centre((4, 3))), None);
+    }
+
+    #[test]
+    fn already_there() {
+        let grid = walled();
+        let pal = Palette::default();
+        let from = Point2::new(40., 40.);
+        let to = Point2::new(50., 36.);
+
+        assert_eq!(grid.find_path(&pal, from, to), Some(vec![to]));
+    }
+}
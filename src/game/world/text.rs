@@ -0,0 +1,383 @@
+//! Human-readable level files
+//!
+//! These hold exactly the same data as the binary `.lvl` files but are written as TOML,
+//! so changes to a level can be diffed and reviewed.
+use crate::{
+    util::Point2,
+    obj::{
+        Object,
+        enemy::{Enemy, Patrol, PatrolMode, Waypoint, TACTICS, DEFAULT_TACTICS, ARCHETYPES, DEFAULT_ARCHETYPE},
+        health::Health,
+        decoration::Decoration,
+        weapon::{Weapon, WeaponDrop, WeaponInstance, WEAPONS},
+        pickup::PICKUPS,
+    },
+};
+use ggez::{GameResult, error::GameError};
+
+use std::path::Path;
+use std::fs::File;
+use std::io::{Read, Write};
+
+use super::{Level, Grid, Palette};
+
+/// File extension that makes a level be loaded and saved as text
+pub const TEXT_EXTENSION: &str = "toml";
+
+#[inline]
+pub fn is_text_level(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some(TEXT_EXTENSION)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TextLevel {
+    palette: Vec<String>,
+    /// Every row of the grid as palette indices separated by spaces
+    grid: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start: Option<(f32, f32)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exit: Option<(f32, f32)>,
+    #[serde(default)]
+    intels: Vec<(f32, f32)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    enemies: Vec<TextEnemy>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pickups: Vec<TextPickup>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    decorations: Vec<TextDecoration>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    weapons: Vec<TextWeaponDrop>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TextEnemy {
+    pos: (f32, f32),
+    rot: f32,
+    /// Id in `archetypes.toml`, the default archetype if left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    archetype: Option<String>,
+    health: Health,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weapon: Option<TextWeapon>,
+    /// Id in `tactics.toml`, the default tactics if left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tactics: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    patrol: Option<TextPatrol>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TextPatrol {
+    #[serde(default)]
+    mode: PatrolMode,
+    waypoints: Vec<TextWaypoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TextWaypoint {
+    pos: (f32, f32),
+    #[serde(default, skip_serializing_if = "is_zero")]
+    wait: f32,
+    #[serde(default, skip_serializing_if = "is_false")]
+    look_around: bool,
+}
+
+#[inline]
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_zero(n: &f32) -> bool {
+    *n == 0.
+}
+#[inline]
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TextWeapon {
+    id: String,
+    cur_clip: u16,
+    ammo: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TextPickup {
+    pos: (f32, f32),
+    id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TextDecoration {
+    pos: (f32, f32),
+    rot: f32,
+    spr: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TextWeaponDrop {
+    pos: (f32, f32),
+    id: String,
+    cur_clip: u16,
+    ammo: u16,
+}
+
+#[inline]
+fn p((x, y): (f32, f32)) -> Point2 {
+    Point2::new(x, y)
+}
+#[inline]
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn t(p: &Point2) -> (f32, f32) {
+    (p.x, p.y)
+}
+
+/// Finds the id in `specs.toml` of a weapon
+pub fn weapon_id(weapon: &Weapon) -> GameResult<&'static str> {
+    WEAPONS.iter()
+        .find(|(_, wep)| wep.name == weapon.name)
+        .map(|(id, _)| &**id)
+        .ok_or_else(|| GameError::ResourceLoadError(format!("No id for weapon {}", weapon.name)))
+}
+
+fn get_weapon(id: &str) -> GameResult<&'static Weapon> {
+    WEAPONS.get(id).ok_or_else(|| GameError::ResourceLoadError(format!("Unknown weapon {:?}", id)))
+}
+
+impl TextLevel {
+    fn from_level(level: &Level) -> GameResult<Self> {
+        let palette = (0..level.palette.len() as u8).filter_map(|i| level.palette.get(i)).map(ToOwned::to_owned).collect();
+        let grid = if level.grid.width == 0 {
+            Vec::new()
+        } else {
+            level.grid.mats
+                .chunks(level.grid.width as usize)
+                .map(|row| row.iter().map(u8::to_string).collect::<Vec<_>>().join(" "))
+                .collect()
+        };
+
+        let mut enemies = Vec::with_capacity(level.enemies.len());
+        for enemy in &level.enemies {
+            enemies.push(TextEnemy {
+                pos: t(&enemy.pl.obj.pos),
+                rot: enemy.pl.obj.rot,
+                archetype: if &*enemy.archetype.id == DEFAULT_ARCHETYPE {
+                    None
+                } else {
+                    Some(enemy.archetype.id.to_string())
+                },
+                health: enemy.pl.health,
+                weapon: match enemy.pl.wep {
+                    Some(wep) => Some(TextWeapon {
+                        id: weapon_id(wep.weapon)?.to_owned(),
+                        cur_clip: wep.cur_clip,
+                        ammo: wep.ammo,
+                    }),
+                    None => None,
+                },
+                tactics: if &*enemy.tactics.id == DEFAULT_TACTICS {
+                    None
+                } else {
+                    Some(enemy.tactics.id.to_string())
+                },
+                patrol: if enemy.patrol.is_empty() {
+                    None
+                } else {
+                    Some(TextPatrol {
+                        mode: enemy.patrol.mode,
+                        waypoints: enemy.patrol.waypoints.iter().map(|wp| TextWaypoint {
+                            pos: t(&wp.pos),
+                            wait: wp.wait,
+                            look_around: wp.look_around,
+                        }).collect(),
+                    })
+                },
+            });
+        }
+        let mut weapons = Vec::with_capacity(level.weapons.len());
+        for drop in &level.weapons {
+            weapons.push(TextWeaponDrop {
+                pos: t(&drop.pos),
+                id: weapon_id(drop.weapon)?.to_owned(),
+                cur_clip: drop.cur_clip,
+                ammo: drop.ammo,
+            });
+        }
+
+        Ok(TextLevel {
+            palette,
+            grid,
+            start: level.start_point.as_ref().map(t),
+            exit: level.exit.as_ref().map(t),
+            intels: level.intels.iter().map(t).collect(),
+            enemies,
+            pickups: level.pickups.iter().map(|(pos, pickup)| TextPickup{pos: t(pos), id: pickup.id.to_string()}).collect(),
+            decorations: level.decorations.iter().map(|dec| TextDecoration {
+                pos: t(&dec.obj.pos),
+                rot: dec.obj.rot,
+                spr: dec.spr.to_string(),
+            }).collect(),
+            weapons,
+        })
+    }
+    fn into_level(self) -> GameResult<Level> {
+        let TextLevel{palette, grid, start, exit, intels, enemies, pickups, decorations, weapons} = self;
+
+        let palette = Palette::new(palette.into_iter().map(|s| &*Box::leak(s.into_boxed_str())).collect());
+
+        let mut width = 0;
+        let mut mats = Vec::new();
+        for (y, row) in grid.iter().enumerate() {
+            let len = mats.len();
+            for mat in row.split_whitespace() {
+                let mat: u8 = mat.parse()
+                    .map_err(|e| GameError::ResourceLoadError(format!("Bad material in row {}: {}", y, e)))?;
+                if mat as usize >= palette.len() {
+                    return Err(GameError::ResourceLoadError(format!("Material {} in row {} is not in the palette", mat, y)));
+                }
+                mats.push(mat);
+            }
+            let row_width = mats.len() - len;
+            if y == 0 {
+                width = row_width;
+            } else if row_width != width {
+                return Err(GameError::ResourceLoadError(format!("Row {} is {} wide, expected {}", y, row_width, width)));
+            }
+        }
+
+        let mut level_enemies = Vec::with_capacity(enemies.len());
+        for TextEnemy{pos, rot, archetype, health, weapon, tactics, patrol} in enemies {
+            let mut enemy = Enemy::new(Object::with_rot(p(pos), rot));
+            if let Some(id) = archetype {
+                enemy.archetype = ARCHETYPES.get(&id).ok_or_else(|| GameError::ResourceLoadError(format!("Unknown archetype {:?}", id)))?;
+            }
+            enemy.pl.health = health;
+            if let Some(TextWeapon{id, cur_clip, ammo}) = weapon {
+                enemy.pl.wep = Some(WeaponInstance::from_drop(WeaponDrop {
+                    pos: p(pos),
+                    weapon: get_weapon(&id)?,
+                    cur_clip,
+                    ammo,
+                }));
+            }
+            if let Some(id) = tactics {
+                enemy.tactics = TACTICS.get(&id).ok_or_else(|| GameError::ResourceLoadError(format!("Unknown tactics {:?}", id)))?;
+            }
+            if let Some(TextPatrol{mode, waypoints}) = patrol {
+                enemy.patrol = Patrol {
+                    mode,
+                    waypoints: waypoints.into_iter().map(|TextWaypoint{pos, wait, look_around}| Waypoint {
+                        pos: p(pos),
+                        wait,
+                        look_around,
+                    }).collect(),
+                };
+            }
+            level_enemies.push(enemy);
+        }
+        let mut level_pickups = Vec::with_capacity(pickups.len());
+        for TextPickup{pos, id} in pickups {
+            let pickup = PICKUPS.get(&id).ok_or_else(|| GameError::ResourceLoadError(format!("Unknown pickup {:?}", id)))?;
+            level_pickups.push((p(pos), pickup));
+        }
+        let mut level_weapons = Vec::with_capacity(weapons.len());
+        for TextWeaponDrop{pos, id, cur_clip, ammo} in weapons {
+            level_weapons.push(WeaponDrop {
+                pos: p(pos),
+                weapon: get_weapon(&id)?,
+                cur_clip,
+                ammo,
+            });
+        }
+
+        Ok(Level {
+            palette,
+            grid: Grid {
+                width: width as u16,
+                mats,
+            },
+            start_point: start.map(p),
+            exit: exit.map(p),
+            intels: intels.into_iter().map(p).collect(),
+            enemies: level_enemies,
+            pickups: level_pickups,
+            decorations: decorations.into_iter().map(|TextDecoration{pos, rot, spr}| Decoration::new(Object::with_rot(p(pos), rot), spr)).collect(),
+            weapons: level_weapons,
+        })
+    }
+}
+
+pub fn load(path: &Path) -> GameResult<Level> {
+    let mut s = String::new();
+    File::open(path)?.read_to_string(&mut s)?;
+
+    let text_level: TextLevel = toml::from_str(&s)
+        .map_err(|e| GameError::ResourceLoadError(format!("{}", e)))?;
+    text_level.into_level()
+}
+
+pub fn save(level: &Level, path: &Path) -> GameResult<()> {
+    let s = toml::to_string_pretty(&TextLevel::from_level(level)?)
+        .map_err(|e| GameError::ResourceLoadError(format!("{}", e)))?;
+
+    File::create(path)?.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn from_text(s: &str) -> GameResult<Level> {
+        toml::from_str::<TextLevel>(s)
+            .map_err(|e| GameError::ResourceLoadError(format!("{}", e)))?
+            .into_level()
+    }
+    fn load_error(s: &str) -> String {
+        match from_text(s) {
+            Err(GameError::ResourceLoadError(msg)) => msg,
+            Err(e) => panic!("unexpected error {:?}", e),
+            Ok(_) => panic!("loaded a bad level"),
+        }
+    }
+
+    #[test]
+    fn levels_survive_the_round_trip() {
+        let dir = std::env::temp_dir().join("topskud-text-round-trip");
+        fs::create_dir_all(&dir).unwrap();
+        let (text, direct, through_text) = (dir.join("level.toml"), dir.join("direct.lvl"), dir.join("through_text.lvl"));
+
+        for entry in fs::read_dir("levels").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|s| s.to_str()) != Some("lvl") {
+                continue
+            }
+            let level = Level::load(&path).unwrap();
+            level.save(&direct).unwrap();
+            level.save(&text).unwrap();
+            Level::load(&text).unwrap().save(&through_text).unwrap();
+
+            assert_eq!(fs::read(&direct).unwrap(), fs::read(&through_text).unwrap(), "{} changed going through text", path.display());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn material_outside_palette() {
+        let msg = load_error(r#"
+            palette = ["grass", "wall"]
+            grid = ["0 1", "1 2"]
+        "#);
+        assert_eq!(msg, "Material 2 in row 1 is not in the palette");
+    }
+
+    #[test]
+    fn rows_of_different_widths() {
+        let msg = load_error(r#"
+            palette = ["grass", "wall"]
+            grid = ["0 1 0", "1 1"]
+        "#);
+        assert_eq!(msg, "Row 1 is 2 wide, expected 3");
+    }
+}
@@ -27,6 +27,10 @@ impl<T> Button<T> {
             height: rect.h,
         })
     }
+    #[inline]
+    pub fn set_text(&mut self, text: &str) -> GameResult<()> {
+        self.text.update(0, text).map(|_| ())
+    }
     pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
         self.mesh.draw(ctx, DrawParam::new())?;
         self.text.draw_center(ctx)
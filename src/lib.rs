@@ -0,0 +1,44 @@
+//! Shooter game
+#![warn(clippy::all)]
+
+#[macro_use]
+extern crate serde_derive;
+
+pub mod io;
+pub mod obj;
+pub mod ext;
+pub mod game;
+
+pub mod util {
+    use ggez::graphics::Color;
+    use ggez::{Context, input::keyboard::{self, KeyCode}};
+    pub type Vector2 = nalgebra::Vector2<f32>;
+    pub type Point2 = nalgebra::Point2<f32>;
+
+    pub const TRANS: Color = Color{r:1.,g:1.,b:1.,a:0.5};
+    pub const GREEN: Color = Color{r:0.1,g:0.7,b:0.1,a:1.};
+    pub const RED: Color = Color{r:1.,g:0.,b:0.,a:1.};
+    pub const BLUE: Color = Color{r:0.,g:0.,b:1.,a:1.};
+
+    /// Makes a unit vector from a given direction angle
+    pub fn angle_to_vec(angle: f32) -> Vector2 {
+        let (sin, cos) = angle.sin_cos();
+        Vector2::new(cos, sin)
+    }
+    /// Gets the direction angle on the screen (0 is along the x-axis) of a vector
+    pub fn angle_from_vec(v: Vector2) -> f32 {
+        let x = v.x;
+        let y = v.y;
+
+        y.atan2(x)
+    }
+
+    pub fn ver(ctx: &Context) -> f32 {
+        <f32>::from((keyboard::is_key_pressed(ctx, KeyCode::S) || keyboard::is_key_pressed(ctx, KeyCode::Down)) as i8 -
+            (keyboard::is_key_pressed(ctx, KeyCode::W) || keyboard::is_key_pressed(ctx, KeyCode::Up)) as i8)
+    }
+    pub fn hor(ctx: &Context) -> f32 {
+        <f32>::from((keyboard::is_key_pressed(ctx, KeyCode::D) || keyboard::is_key_pressed(ctx, KeyCode::Right)) as i8 -
+            (keyboard::is_key_pressed(ctx, KeyCode::A) || keyboard::is_key_pressed(ctx, KeyCode::Left)) as i8)
+    }
+}
@@ -2,9 +2,6 @@
 //! Shooter game
 #![warn(clippy::all)]
 
-#[macro_use]
-extern crate serde_derive;
-
 use std::env::args;
 
 use ggez::{
@@ -14,49 +11,27 @@ use ggez::{
     event::run,
 };
 
-pub mod io;
-pub mod obj;
-pub mod ext;
-pub mod game;
-
-pub mod util {
-    use ggez::graphics::Color;
-    use ggez::{Context, input::keyboard::{self, KeyCode}};
-    pub type Vector2 = nalgebra::Vector2<f32>;
-    pub type Point2 = nalgebra::Point2<f32>;
-
-    pub const TRANS: Color = Color{r:1.,g:1.,b:1.,a:0.5};
-    pub const GREEN: Color = Color{r:0.1,g:0.7,b:0.1,a:1.};
-    pub const RED: Color = Color{r:1.,g:0.,b:0.,a:1.};
-    pub const BLUE: Color = Color{r:0.,g:0.,b:1.,a:1.};
-
-    /// Makes a unit vector from a given direction angle
-    pub fn angle_to_vec(angle: f32) -> Vector2 {
-        let (sin, cos) = angle.sin_cos();
-        Vector2::new(cos, sin)
-    }
-    /// Gets the direction angle on the screen (0 is along the x-axis) of a vector
-    pub fn angle_from_vec(v: Vector2) -> f32 {
-        let x = v.x;
-        let y = v.y;
-
-        y.atan2(x)
-    }
-
-    pub fn ver(ctx: &Context) -> f32 {
-        <f32>::from((keyboard::is_key_pressed(ctx, KeyCode::S) || keyboard::is_key_pressed(ctx, KeyCode::Down)) as i8 -
-            (keyboard::is_key_pressed(ctx, KeyCode::W) || keyboard::is_key_pressed(ctx, KeyCode::Up)) as i8)
-    }
-    pub fn hor(ctx: &Context) -> f32 {
-        <f32>::from((keyboard::is_key_pressed(ctx, KeyCode::D) || keyboard::is_key_pressed(ctx, KeyCode::Right)) as i8 -
-            (keyboard::is_key_pressed(ctx, KeyCode::A) || keyboard::is_key_pressed(ctx, KeyCode::Left)) as i8)
-    }
-}
-
-use self::game::Master;
+use topskud::game::{Master, world::Level, difficulty::Difficulty};
 
 fn main() {
-    let mut args = args().skip(1);
+    let mut args: Vec<_> = args().skip(1).collect();
+
+    let mut difficulty = Difficulty::default();
+    if let Some(i) = args.iter().position(|arg| arg == "--difficulty") {
+        args.remove(i);
+        if i >= args.len() {
+            eprintln!("Usage: topskud --difficulty <easy|normal|hard|insane> [file]");
+            return
+        }
+        match args.remove(i).parse() {
+            Ok(d) => difficulty = d,
+            Err(e) => {
+                eprintln!("{}", e);
+                return
+            }
+        }
+    }
+    let mut args = args.into_iter();
 
     let arg;
     if let Some(p) = args.next() {
@@ -65,6 +40,17 @@ fn main() {
         arg = "".to_owned();
     };
 
+    // Convert between level formats without opening a window
+    if arg == "--convert" {
+        match (args.next(), args.next()) {
+            (Some(from), Some(to)) => if let Err(e) = Level::convert(&from, &to) {
+                eprintln!("Couldn't convert {} to {}: {}", from, to, e);
+            },
+            _ => eprintln!("Usage: topskud --convert <from> <to>"),
+        }
+        return
+    }
+
     // Set window mode
     let window_mode = conf::WindowMode::default().dimensions(1152., 648.);
 
@@ -83,7 +69,7 @@ fn main() {
     }
 
     // Tries to create a game state and runs it if succesful
-    match Master::new(&mut ctx, &arg) {
+    match Master::new(&mut ctx, &arg, difficulty) {
         Err(e) => {
             eprintln!("Couldn't load game {}", e);
         }
@@ -1,61 +1,172 @@
 use ggez::{Context, GameResult, graphics::WHITE};
 
 use crate::{
-    util::angle_to_vec,
+    util::{angle_to_vec, angle_from_vec, Vector2, Point2},
     game::{
         DELTA,
-        world::{Grid, Palette},
+        world::{Grid, Palette, bullet_resistance, ricochet_chance},
     },
     io::tex::{Assets, }
 };
-use super::{Object, player::Player, enemy::Enemy, health::Health, weapon::Weapon};
+use super::{Object, BODY_RADIUS, player::Player, enemy::Enemy, weapon::Weapon, decoration::Decoration};
+use rand::Rng;
 
 #[derive(Debug, Clone)]
 pub struct Bullet<'a> {
     pub obj: Object,
     pub weapon: &'a Weapon,
+    /// Penetration left for going through walls, starting at the weapon's penetration
+    pub power: f32,
+    /// Fraction of the weapon's damage left after going through walls
+    pub damage: f32,
 }
 
-const SPEED: f32 = 1200.;
+/// Pixels per second a bullet flies unless its weapon says otherwise
+pub const SPEED: f32 = 1200.;
 
-impl Bullet<'_> {
+/// Sine of the largest angle to a wall a bullet can ricochet off it at
+const RICOCHET_ANGLE_SIN: f32 = 0.5;
+/// Fraction of the damage a bullet keeps after ricocheting
+const RICOCHET_DAMAGE: f32 = 0.6;
+
+/// Radius of the head in the middle of a body
+const HEAD_RADIUS: f32 = 6.;
+/// How far the torso reaches out to the sides, the rest is arms
+const TORSO_WIDTH: f32 = 12.;
+/// How far the torso reaches out to the front and back, the rest is arms and the weapon
+const TORSO_DEPTH: f32 = 7.;
+
+/// A part of a body that can be hit
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BodyPart {
+    Head,
+    Torso,
+    Limbs,
+}
+
+impl BodyPart {
+    /// The part of the body a line passes through closest to its middle, `None` if it misses the body
+    ///
+    /// Seen from above the head is in the middle, with the torso around it and arms
+    /// sticking out to the sides and to the front, going by which way the body faces.
+    pub fn hit(body: &Object, line_start: Point2, line_dist: Vector2) -> Option<Self> {
+        let to_line = -Grid::distance_line_circle(line_start, line_dist, body.pos);
+        let dist = to_line.norm();
+        if dist > BODY_RADIUS {
+            None
+        } else if dist <= HEAD_RADIUS {
+            Some(BodyPart::Head)
+        } else {
+            let facing = angle_to_vec(body.rot);
+            let forward = to_line.dot(&facing);
+            let side = to_line.x * facing.y - to_line.y * facing.x;
+            if forward.abs() <= TORSO_DEPTH && side.abs() <= TORSO_WIDTH {
+                Some(BodyPart::Torso)
+            } else {
+                Some(BodyPart::Limbs)
+            }
+        }
+    }
+}
+
+impl<'a> Bullet<'a> {
     #[inline]
-    pub fn apply_damage(&self, health: &mut Health) {
-        health.weapon_damage(self.weapon.damage, self.weapon.penetration)
+    pub fn new(obj: Object, weapon: &'a Weapon) -> Self {
+        Bullet {
+            obj,
+            weapon,
+            power: weapon.penetration,
+            damage: 1.,
+        }
+    }
+    #[inline]
+    pub fn apply_damage(&self, pl: &mut Player, scale: f32, part: BodyPart) {
+        self.weapon.hurt(pl, scale * self.damage * self.weapon.multipliers.get(part) * self.weapon.damage)
     }
     #[inline]
     pub fn draw(&self, ctx: &mut Context, a: &Assets) -> GameResult<()> {
-        let img = a.get_img(ctx, "common/bullet");
+        let img = a.get_img(ctx, &self.weapon.projectile_sprite);
         self.obj.draw(ctx, &*img, WHITE)
     }
-    pub fn update(&mut self, palette: &Palette, grid: &Grid, player: &mut Player, enemies: &mut [Enemy]) -> Hit {
+    /// Moves the bullet, hurting the first of the targets it hits
+    ///
+    /// Whether it ricochets off a wall is decided by `rng`.
+    pub fn update<R: Rng>(&mut self, palette: &Palette, grid: &Grid, targets: Targets<'_>, rng: &mut R) -> Hit {
+        let Targets{player, player_damage, enemies, decorations} = targets;
         let start = self.obj.pos;
-        let d_pos = SPEED * DELTA * angle_to_vec(self.obj.rot);
+        let d_pos = self.weapon.projectile_speed * DELTA * angle_to_vec(self.obj.rot);
 
-        if Grid::dist_line_circle(start, d_pos, player.obj.pos) <= 16. {
-            self.apply_damage(&mut player.health);
-            return Hit::Player;
+        if let Some(part) = BodyPart::hit(&player.obj, start, d_pos) {
+            self.apply_damage(player, player_damage, part);
+            return Hit::Player(part);
         }
         for (i, enem) in enemies.iter_mut().enumerate() {
-            if Grid::dist_line_circle(start, d_pos, enem.pl.obj.pos) <= 16. {
-                self.apply_damage(&mut enem.pl.health);
-                return Hit::Enemy(i);
+            if let Some(part) = BodyPart::hit(&enem.pl.obj, start, d_pos) {
+                let scale = enem.pl.effects.damage_factor();
+                self.apply_damage(&mut enem.pl, scale, part);
+                return Hit::Enemy(i, part);
+            }
+        }
+        for (i, dec) in decorations.iter_mut().enumerate() {
+            if let Some(radius) = dec.solid_radius() {
+                if Grid::dist_line_circle(start, d_pos, dec.obj.pos) <= radius {
+                    let destroyed = dec.take_damage(self.damage * self.weapon.damage);
+                    return Hit::Prop{index: i, destroyed};
+                }
             }
         }
         let cast = grid.ray_cast(palette, start, d_pos, true);
         self.obj.pos = cast.into_point();
         if cast.full() {
-            Hit::None
-        } else {
-            Hit::Wall
+            return Hit::None;
+        }
+        let dir = angle_to_vec(self.obj.rot);
+        let at = self.obj.pos;
+        let mat = grid.material(palette, at + 0.01 * dir);
+
+        // Hitting a wall at a shallow enough angle can send it off the other way
+        if let Some(to_wall) = cast.half_vec() {
+            let normal = to_wall.normalize();
+            let chance = ricochet_chance(mat);
+            if chance > 0. && dir.dot(&normal).abs() <= RICOCHET_ANGLE_SIN && rng.gen::<f32>() < chance {
+                let reflected = dir - 2. * dir.dot(&normal) * normal;
+                self.obj.rot = angle_from_vec(reflected);
+                self.obj.pos -= 0.01 * normal;
+                self.damage *= RICOCHET_DAMAGE;
+                return Hit::Ricochet{at, mat, to_wall: normal};
+            }
+        }
+
+        let resistance = bullet_resistance(mat);
+        // Going through takes it to the other side of the tile, the rest of the way is flown next time
+        let through = cast.half() && resistance < 1. && self.power > resistance;
+        if through {
+            self.power -= resistance;
+            self.damage *= 1. - resistance;
+            self.obj.pos = Grid::tile_exit(at + 0.01 * dir, dir) + 0.01 * dir;
         }
+        Hit::Wall{at, mat, through}
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// What a bullet can hit besides walls
+pub struct Targets<'a> {
+    pub player: &'a mut Player,
+    /// Factor on the damage done to the player
+    pub player_damage: f32,
+    pub enemies: &'a mut [Enemy],
+    pub decorations: &'a mut [Decoration],
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Hit {
-    Wall,
-    Player,
-    Enemy(usize),
+    /// A wall of the material at the point, and whether the bullet went through it
+    Wall{at: Point2, mat: &'static str, through: bool},
+    /// A wall of the material at the point which the bullet bounced off, with the direction into the wall
+    Ricochet{at: Point2, mat: &'static str, to_wall: Vector2},
+    Player(BodyPart),
+    Enemy(usize, BodyPart),
+    /// A solid decoration, and whether that destroyed it
+    Prop{index: usize, destroyed: bool},
     None,
 }
\ No newline at end of file
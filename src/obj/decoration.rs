@@ -1,9 +1,69 @@
 use crate::{
     io::tex::{Assets, },
+    game::{DELTA, world::{Grid, Palette}},
 };
 use ggez::{Context, GameResult, graphics::Color};
+use lazy_static::lazy_static;
 
-use super::Object;
+use std::fs::File;
+use std::io::Read;
+use std::collections::HashMap;
+
+use super::{Object, grenade::{GrenadeType, GrenadeTemplate}};
+
+lazy_static!{
+    /// Decorations that can be shot, broken, blown up or walked into, by sprite
+    pub static ref PROPS: HashMap<String, Prop> = {
+        let mut file = File::open("resources/decorations/props.toml").expect("props.toml file");
+        let mut file_contents = String::new();
+        file.read_to_string(&mut file_contents).expect("Reading to succeed");
+
+        let templates: HashMap<String, PropTemplate> = toml::from_str(&file_contents).expect("well-defined props");
+        templates.into_iter().map(|(k, v)| {
+            let prop = v.build(&k);
+            (k, prop)
+        }).collect()
+    };
+}
+
+/// What a decoration does besides being drawn, from `resources/decorations/props.toml`
+#[derive(Debug, Clone)]
+pub struct Prop {
+    /// Damage it takes to destroy it, it can't be destroyed without
+    pub health: Option<f32>,
+    /// Sprite it's drawn with once destroyed, it disappears without one
+    pub destroyed: Option<Box<str>>,
+    /// Radius of the circle blocking bullets and movement while it's intact
+    pub solid: Option<f32>,
+    /// What it blows up like when destroyed
+    pub explosion: Option<GrenadeType>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PropTemplate {
+    health: Option<f32>,
+    destroyed: Option<Box<str>>,
+    solid: Option<f32>,
+    explosion: Option<GrenadeTemplate>,
+}
+
+impl PropTemplate {
+    pub(crate) fn build(self, id: &str) -> Prop {
+        let PropTemplate {
+            health,
+            destroyed,
+            solid,
+            explosion,
+        } = self;
+
+        Prop {
+            health,
+            destroyed,
+            solid,
+            explosion: explosion.map(|explosion| explosion.build(id)),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct OldDecoration {
@@ -15,10 +75,7 @@ impl OldDecoration {
     pub fn renew(self) -> Decoration {
         let OldDecoration{obj, i} = self;
 
-        Decoration {
-            obj,
-            spr: OLD_DECORATION_LIST[i].into()
-        }
+        Decoration::new(obj, OLD_DECORATION_LIST[i])
     }
 }
 
@@ -44,6 +101,11 @@ const OLD_DECORATION_LIST: [&str; 15] = [
 pub struct Decoration {
     pub obj: Object,
     pub spr: Box<str>,
+    /// Damage taken so far, only props with health can take any
+    #[serde(skip)]
+    pub damage: f32,
+    #[serde(skip)]
+    pub destroyed: bool,
 }
 
 impl Decoration {
@@ -51,16 +113,69 @@ impl Decoration {
     pub fn new<S: Into<Box<str>>>(obj: Object, spr: S) -> Self {
         Decoration {
             obj,
-            spr: spr.into()
+            spr: spr.into(),
+            damage: 0.,
+            destroyed: false,
         }
     }
     #[inline]
     pub fn draw(&self, ctx: &mut Context, a: &Assets, color: Color) -> GameResult<()> {
-        let img = a.get_img(ctx, &self.spr);
+        let spr = match self.prop() {
+            Some(Prop{destroyed: Some(spr), ..}) if self.destroyed => spr,
+            _ => &self.spr,
+        };
+        let img = a.get_img(ctx, spr);
         self.obj.draw(ctx, &*img, color)
     }
     #[inline]
+    pub fn prop(&self) -> Option<&'static Prop> {
+        PROPS.get(&*self.spr)
+    }
+    /// What it blows up like when destroyed if it's explosive
+    #[inline]
+    pub fn explosive(&self) -> Option<&'static GrenadeType> {
+        self.prop().and_then(|prop| prop.explosion.as_ref())
+    }
+    /// Radius of the circle blocking bullets and movement, `None` if nothing is blocked
+    #[inline]
+    pub fn solid_radius(&self) -> Option<f32> {
+        if self.destroyed {
+            None
+        } else {
+            self.prop().and_then(|prop| prop.solid)
+        }
+    }
+    #[inline]
     pub fn is_solid(&self) -> bool {
-        &*self.spr == "decorations/trashcan"
+        self.solid_radius().is_some()
     }
-}
\ No newline at end of file
+    /// Whether nothing is left of it once destroyed
+    #[inline]
+    pub fn vanishes(&self) -> bool {
+        self.destroyed && self.prop().map(|prop| prop.destroyed.is_none()).unwrap_or(true)
+    }
+    /// Damages it if it can be destroyed, returns whether this destroyed it
+    pub fn take_damage(&mut self, damage: f32) -> bool {
+        if self.destroyed {
+            return false
+        }
+        match self.prop().and_then(|prop| prop.health) {
+            Some(health) => {
+                self.damage += damage;
+                self.destroyed = self.damage >= health;
+                self.destroyed
+            }
+            None => false,
+        }
+    }
+    /// Pushes a body with the radius out of it if it's solid, stopping at walls
+    pub fn push_out(&self, obj: &mut Object, radius: f32, palette: &Palette, grid: &Grid) {
+        if let Some(solid) = self.solid_radius() {
+            let dist = obj.pos - self.obj.pos;
+            let overlap = solid + radius - dist.norm();
+            if dist.norm_squared() > 0. && overlap > 0. {
+                obj.move_on_grid(dist.normalize(), overlap / DELTA, palette, grid);
+            }
+        }
+    }
+}
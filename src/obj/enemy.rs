@@ -1,20 +1,24 @@
-use crate::util::{BLUE, Vector2, Point2};
+use crate::util::{BLUE, RED, Vector2, Point2};
 
 use ggez::{
     Context, GameResult,
-    graphics::{self, Mesh, Color, DrawMode, DrawParam},
+    graphics::{self, Mesh, Color, DrawMode, DrawParam, Rect},
 };
 
 use crate::{
     util::{angle_from_vec, angle_to_vec},
-    io::{
-        snd::MediaPlayer,
-        tex::{Assets, },
-    },
-    game::{DELTA, world::{Grid, Palette}},
+    io::{tex::{Assets, }, save::Point2Def},
+    game::{DELTA, sim::Event, difficulty::Difficulty, world::{Grid, Palette, Noise}},
 };
 
-use super::{Object, player::Player};
+use rand::Rng;
+
+use super::{Object, player::Player, pickup::Pickup, grenade::Area, bullet::SPEED as BULLET_SPEED};
+
+mod tactics;
+pub use self::tactics::*;
+mod archetype;
+pub use self::archetype::*;
 
 #[derive(Debug, Clone)]
 pub enum Chaser {
@@ -25,7 +29,19 @@ pub enum Chaser {
     },
     LookAround {
         dir: Vector2,
-    }
+    },
+    /// Hiding from the player while reloading
+    TakeCover {
+        pos: Point2,
+    },
+    /// Staying out of sight because of low health
+    Retreat {
+        pos: Point2,
+    },
+    /// Going for a health pickup
+    Heal {
+        pos: Point2,
+    },
 }
 
 impl Chaser {
@@ -35,6 +51,10 @@ impl Chaser {
             _ => false,
         }
     }
+    /// Whether the enemy is busy with something its tactics decided on and shouldn't be distracted
+    pub fn tactical(&self) -> bool {
+        matches!(self, Chaser::TakeCover{..} | Chaser::Retreat{..} | Chaser::Heal{..})
+    }
 }
 
 impl Default for Chaser {
@@ -43,26 +63,194 @@ impl Default for Chaser {
     }
 }
 
+/// How much an enemy knows about the player being around
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Awareness {
+    #[default]
+    Unaware,
+    /// Has seen or heard something and will look into it, but won't shoot yet
+    Suspicious,
+    /// Knows the player is there, shoots on sight and calls for help
+    Alerted,
+}
+
+/// Seconds it takes to notice the player right in front
+const NOTICE_TIME_NEAR: f32 = 0.25;
+/// Seconds it takes to notice the player at the edge of the vision range
+const NOTICE_TIME_FAR: f32 = 2.;
+/// Suspicion lost per second while suspicious and not seeing anything
+const SUSPICION_DECAY: f32 = 0.2;
+/// Suspicion lost per second while alerted and not chasing anything
+const ALERT_DECAY: f32 = 0.05;
+/// Suspicion from hearing a noise
+const HEARD_SUSPICION: f32 = 0.5;
+/// Seconds before looking again for pickups or a place to hide after finding none
+const SEARCH_COOLDOWN: f32 = 1.;
+
+/// What an enemy does after reaching the last waypoint of its patrol
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatrolMode {
+    /// Go back to the first waypoint
+    #[default]
+    Loop,
+    /// Walk the waypoints in reverse order
+    PingPong,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Waypoint {
+    #[serde(with = "Point2Def")]
+    pub pos: Point2,
+    /// Seconds to stay at the waypoint before walking on
+    pub wait: f32,
+    /// Whether to look from side to side while waiting
+    pub look_around: bool,
+}
+
+impl Waypoint {
+    #[inline]
+    pub fn new(pos: Point2) -> Self {
+        Waypoint {
+            pos,
+            wait: 0.,
+            look_around: false,
+        }
+    }
+}
+
+/// The route an enemy walks while it doesn't know where the player is
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Patrol {
+    pub mode: PatrolMode,
+    pub waypoints: Vec<Waypoint>,
+}
+
+impl Patrol {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.waypoints.is_empty()
+    }
+}
+
+/// How far along its patrol an enemy is
+#[derive(Debug, Clone, Copy, Default)]
+struct PatrolState {
+    next: usize,
+    backwards: bool,
+    waited: f32,
+    /// Rotation when arriving at the current waypoint
+    arrival_rot: f32,
+}
+
+impl PatrolState {
+    fn advance(&mut self, patrol: &Patrol) {
+        let len = patrol.waypoints.len();
+        self.waited = 0.;
+        match patrol.mode {
+            PatrolMode::Loop => self.next = (self.next + 1) % len,
+            PatrolMode::PingPong if len < 2 => self.next = 0,
+            PatrolMode::PingPong => {
+                if (self.backwards && self.next == 0) || (!self.backwards && self.next + 1 >= len) {
+                    self.backwards = !self.backwards;
+                }
+                if self.backwards {
+                    self.next -= 1;
+                } else {
+                    self.next += 1;
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Enemy {
     pub pl: Player,
+    #[serde(skip, default = "Archetype::default_archetype")]
+    pub archetype: &'static Archetype,
+    #[serde(default)]
+    pub patrol: Patrol,
+    #[serde(skip)]
+    patrol_state: PatrolState,
+    #[serde(skip, default = "Tactics::default_tactics")]
+    pub tactics: &'static Tactics,
     #[serde(skip)]
     pub behaviour: Chaser,
+    #[serde(skip)]
+    pub awareness: Awareness,
+    /// From 0 to 1, the enemy is alerted when it's full
+    #[serde(skip)]
+    pub suspicion: f32,
+    /// Where the player was last seen while suspicious
+    #[serde(skip)]
+    glimpse: Option<Point2>,
+    /// Where the player was last seen while alerted
+    #[serde(skip)]
+    threat: Option<Point2>,
+    /// How long the player has been in sight
+    #[serde(skip)]
+    sight_time: f32,
+    /// Radians the aim is off by, shrinks the longer the player stays in sight
+    #[serde(skip)]
+    aim_error: f32,
+    #[serde(skip)]
+    reloading: bool,
+    /// Seconds until it looks for health pickups again
+    #[serde(skip)]
+    heal_search: f32,
+    /// Seconds until it looks for cover or a place to retreat to again
+    #[serde(skip)]
+    hide_search: f32,
+    /// Waypoints to the position being chased, the next one is last
+    #[serde(skip)]
+    pub path: Vec<Point2>,
 }
 
-pub const VISIBILITY: f32 = ::std::f32::consts::FRAC_PI_4;
+/// Fraction of its speed an enemy walks its patrol at
+const PATROL_PACE: f32 = 0.6;
+/// Most radians an enemy can be turned away from where it's aiming and still shoot
+const FIRING_ANGLE: f32 = 0.2;
+/// Cosine of the largest angle between where an enemy faces and the direction to an attacker
+/// that still counts as facing away from it
+const FACING_AWAY_COS: f32 = -0.5;
 
 impl Enemy {
     pub fn new(obj: Object) -> Enemy {
         Enemy {
             pl: Player::new(obj),
+            archetype: Archetype::default_archetype(),
+            patrol: Patrol::default(),
+            patrol_state: PatrolState::default(),
+            tactics: Tactics::default_tactics(),
             behaviour: Chaser::NoIntel,
+            awareness: Awareness::Unaware,
+            suspicion: 0.,
+            glimpse: None,
+            threat: None,
+            sight_time: 0.,
+            aim_error: 0.,
+            reloading: false,
+            heal_search: 0.,
+            hide_search: 0.,
+            path: Vec::new(),
         }
     }
-    pub fn draw_visibility_cone(&self, ctx: &mut Context, length: f32) -> GameResult<()> {
+    /// An enemy of an archetype with its health, tactics and weapon
+    pub fn from_archetype(obj: Object, archetype: &'static Archetype) -> Enemy {
+        let mut enemy = Enemy::new(obj);
+        enemy.archetype = archetype;
+        enemy.tactics = archetype.tactics;
+        enemy.pl.health = archetype.health;
+        enemy.pl.wep = archetype.weapon.map(|wep| wep.make_instance());
+        enemy
+    }
+    /// Draws the vision cone as long as the enemy can see
+    pub fn draw_visibility_cone(&self, ctx: &mut Context) -> GameResult<()> {
         let Object{pos, rot} = self.pl.obj;
-        let dir1 = angle_to_vec(rot - VISIBILITY);
-        let dir2 = angle_to_vec(rot + VISIBILITY);
+        let length = self.archetype.vision_range;
+        let dir1 = angle_to_vec(rot - self.archetype.vision_angle);
+        let dir2 = angle_to_vec(rot + self.archetype.vision_angle);
 
         let mesh = Mesh::new_polyline(ctx, DrawMode::stroke(1.5), &[pos + (length * dir1), pos, pos + (length * dir2)], BLUE)?;
 
@@ -70,20 +258,95 @@ impl Enemy {
     }
     #[inline]
     pub fn draw(&self, ctx: &mut Context, a: &Assets, color: Color) -> GameResult<()> {
-        self.pl.draw(ctx, a, "common/enemy", color)
+        self.pl.draw(ctx, a, &self.archetype.sprite, color)
+    }
+    /// Draws the suspicion meter above the enemy unless it's unaware
+    pub fn draw_awareness(&self, ctx: &mut Context) -> GameResult<()> {
+        const YELLOW: Color = Color{r: 1., g: 1., b: 0., a: 1.};
+        let color = match self.awareness {
+            Awareness::Unaware => return Ok(()),
+            Awareness::Suspicious => YELLOW,
+            Awareness::Alerted => RED,
+        };
+        let pos = self.pl.obj.pos;
+        let rect = Rect{x: pos.x - 12., y: pos.y - 26., w: 24. * self.suspicion, h: 4.};
+
+        let mesh = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, color)?;
+        graphics::draw(ctx, &mesh, DrawParam::default())
+    }
+    #[inline]
+    pub fn alert(&mut self) {
+        self.awareness = Awareness::Alerted;
+        self.suspicion = 1.;
+    }
+    /// Makes the meter go up if the player (at the position and with the velocity) is in sight and down otherwise
+    ///
+    /// How far off its first shots will be is decided by `rng`.
+    pub fn watch<R: Rng>(&mut self, player: Option<(Point2, Vector2)>, difficulty: Difficulty, rng: &mut R) {
+        let pos = self.pl.obj.pos;
+        if let Some((player_pos, vel)) = player {
+            if self.sight_time == 0. {
+                // Just spotted, the first shots will be off
+                self.aim_error = rng.gen_range(-1., 1.) * self.archetype.aim_error;
+            }
+            self.sight_time += DELTA;
+            if self.aim_error != 0. {
+                let steady = self.archetype.aim_error / self.archetype.aim_time * DELTA;
+                self.aim_error = if self.aim_error.abs() <= steady { 0. } else { self.aim_error - steady * self.aim_error.signum() };
+            }
+
+            let closeness = ((player_pos - pos).norm() / (self.archetype.vision_range * difficulty.vision_range())).min(1.);
+            let notice_time = NOTICE_TIME_NEAR + (NOTICE_TIME_FAR - NOTICE_TIME_NEAR) * closeness;
+            self.suspicion = (self.suspicion + DELTA / notice_time).min(1.);
+
+            if self.suspicion >= 1. {
+                self.awareness = Awareness::Alerted;
+            } else if self.awareness == Awareness::Unaware {
+                self.awareness = Awareness::Suspicious;
+            }
+
+            if self.awareness == Awareness::Alerted {
+                self.glimpse = None;
+                self.threat = Some(player_pos);
+                if !self.behaviour.tactical() {
+                    self.behaviour = Chaser::LastKnown{pos: player_pos, vel};
+                }
+            } else {
+                // Stop and stare
+                self.glimpse = Some(player_pos);
+                self.behaviour = Chaser::LookAround{dir: player_pos - pos};
+            }
+        } else {
+            self.sight_time = 0.;
+            if let Some(glimpse) = self.glimpse.take() {
+                // Go and have a closer look
+                self.behaviour = Chaser::LastKnown{pos: glimpse, vel: glimpse - pos};
+            }
+            if !self.behaviour.chasing() {
+                let decay = match self.awareness {
+                    Awareness::Alerted => ALERT_DECAY,
+                    _ => SUSPICION_DECAY,
+                };
+                self.suspicion = (self.suspicion - decay * DELTA).max(0.);
+                if self.suspicion <= 0. {
+                    self.awareness = Awareness::Unaware;
+                    self.threat = None;
+                }
+            }
+        }
     }
     fn look_towards(&mut self, dist: Vector2) -> bool{
         let dir = angle_to_vec(self.pl.obj.rot);
 
         let rotation = dir.angle(&dist);
 
-        const ROTATION: f32 = 6. * DELTA;
+        let turn = self.archetype.turn_speed * DELTA;
 
-        if rotation > ROTATION {
+        if rotation > turn {
             if dir.perp(&dist) > 0. {
-                self.pl.obj.rot += ROTATION;
+                self.pl.obj.rot += turn;
             } else {
-                self.pl.obj.rot -= ROTATION;
+                self.pl.obj.rot -= turn;
             }
             false
         } else {
@@ -91,44 +354,266 @@ impl Enemy {
             true
         }
     }
-    pub fn update(&mut self, ctx: &mut Context, mplayer: &mut MediaPlayer) -> GameResult<()> {
+    /// Makes sure the path leads to `target`, finding a new one if it has moved to another tile
+    fn path_to(&mut self, target: Point2, palette: &Palette, grid: &Grid) {
+        match self.path.first_mut() {
+            Some(end) if Grid::snap(*end) == Grid::snap(target) => *end = target,
+            _ => {
+                self.path = grid.find_path(palette, self.pl.obj.pos, target).unwrap_or_default();
+                self.path.reverse();
+            }
+        }
+    }
+    /// Walks along the path and returns the direction walked, `None` if the end has been reached
+    fn walk(&mut self, speed: f32, palette: &Palette, grid: &Grid) -> Option<Vector2> {
+        while let Some(&next) = self.path.last() {
+            if (next - self.pl.obj.pos).norm() < speed * DELTA {
+                self.path.pop();
+            } else {
+                break
+            }
+        }
+
+        let to_next = *self.path.last()? - self.pl.obj.pos;
+        self.pl.obj.move_on_grid(to_next.normalize(), speed, palette, grid);
+        Some(to_next)
+    }
+    fn patrol(&mut self, palette: &Palette, grid: &Grid) {
+        /// Seconds it takes to look to both sides and back
+        const LOOK_PERIOD: f32 = 4.;
+
+        let waypoint = match self.patrol.waypoints.get(self.patrol_state.next) {
+            Some(&waypoint) => waypoint,
+            None => return self.path.clear(),
+        };
+
+        if self.patrol_state.waited == 0. {
+            self.path_to(waypoint.pos, palette, grid);
+            if let Some(dir) = self.walk(PATROL_PACE * self.archetype.speed * self.pl.effects.speed_factor(), palette, grid) {
+                self.look_towards(dir);
+                return
+            }
+            self.patrol_state.arrival_rot = self.pl.obj.rot;
+        }
+        self.patrol_state.waited += DELTA;
+        if waypoint.look_around {
+            let phase = self.patrol_state.waited / LOOK_PERIOD * 2. * ::std::f32::consts::PI;
+            let rot = self.patrol_state.arrival_rot + ::std::f32::consts::FRAC_PI_2 * phase.sin();
+            self.look_towards(angle_to_vec(rot));
+        }
+        if self.patrol_state.waited >= waypoint.wait {
+            self.patrol_state.advance(&self.patrol);
+        }
+    }
+    /// Decides whether to go for a health pickup, retreat or take cover according to its tactics
+    pub fn think(&mut self, pickups: &[Pickup], palette: &Palette, grid: &Grid) {
+        let tactics = self.tactics;
+        let pos = self.pl.obj.pos;
+        let health = self.pl.health;
+
+        self.heal_search = (self.heal_search - DELTA).max(0.);
+        self.hide_search = (self.hide_search - DELTA).max(0.);
+
+        if let Chaser::Heal{..} = self.behaviour {
+            return
+        }
+        if health.hp < tactics.heal_health && self.heal_search == 0. {
+            let healing = pickups.iter()
+                .filter(|pickup| (pickup.pos - pos).norm() <= tactics.search_range)
+                .filter(|pickup| pickup.pickup_type.heals(&health))
+                .filter(|pickup| grid.find_path(palette, pos, pickup.pos).is_some())
+                .min_by(|a, b| (a.pos - pos).norm().partial_cmp(&(b.pos - pos).norm()).unwrap());
+
+            if let Some(pickup) = healing {
+                self.behaviour = Chaser::Heal{pos: pickup.pos};
+                return
+            }
+            self.heal_search = SEARCH_COOLDOWN;
+        }
+
+        let threat = match self.threat {
+            Some(threat) => threat,
+            None => return,
+        };
+        if let Chaser::TakeCover{..} | Chaser::Retreat{..} = self.behaviour {
+            return
+        }
+        if self.hide_search > 0. {
+            return
+        }
+        let hidden = |p: Point2| !grid.ray_cast(palette, p, threat - p, true).full();
+
+        let spot = if health.hp < tactics.retreat_health {
+            let distance = (pos - threat).norm();
+            grid.nearest_tile(palette, pos, tactics.search_range, |p| hidden(p) && (p - threat).norm() > distance)
+                .map(|pos| Chaser::Retreat{pos})
+        } else if tactics.cover_to_reload && self.reloading {
+            grid.nearest_tile(palette, pos, tactics.search_range, hidden)
+                .map(|pos| Chaser::TakeCover{pos})
+        } else {
+            return
+        };
+        match spot {
+            Some(behaviour) => self.behaviour = behaviour,
+            None => self.hide_search = SEARCH_COOLDOWN,
+        }
+    }
+    pub fn update(&mut self, palette: &Palette, grid: &Grid, events: &mut Vec<Event>) {
+        let speed = self.archetype.speed * self.pl.effects.speed_factor();
+
         if let Some(wep) = &mut self.pl.wep {
-            wep.update(ctx, mplayer)?;
+            wep.update(events);
+            if wep.loading_time == 0. {
+                self.reloading = false;
+            }
             if wep.cur_clip == 0 && wep.loading_time == 0. {
-                wep.reload(ctx, mplayer)?;
+                wep.reload(events);
+                self.reloading = wep.loading_time > 0.;
             }
         }
         match self.behaviour {
-            Chaser::NoIntel => (),
+            Chaser::NoIntel => self.patrol(palette, grid),
             Chaser::LastKnown{
                 pos: player_pos,
                 vel
             } => {
-                let dist = player_pos-self.pl.obj.pos;
-                self.look_towards(dist);
-
-                let distance = dist.norm();
-                const CHASE_SPEED: f32 = 100. * DELTA;
+                // Walk back to the patrol from the start when the chase is over
+                self.patrol_state.waited = 0.;
+                self.path_to(player_pos, palette, grid);
+                if let Some(to_next) = self.walk(speed, palette, grid) {
+                    let dist = player_pos - self.pl.obj.pos;
 
-                if distance >= CHASE_SPEED {
-                    let displace = CHASE_SPEED * dist / distance;
-                    self.pl.obj.pos += displace;
+                    // Keep aiming at the player while it's in sight, otherwise look where we're going
+                    if grid.ray_cast(palette, self.pl.obj.pos, dist, true).full() {
+                        let lead = self.lead(player_pos, vel);
+                        self.look_towards(lead);
+                    } else {
+                        self.look_towards(to_next);
+                    }
                 } else {
                     self.behaviour = Chaser::LookAround{dir: vel};
                 }
             }
             Chaser::LookAround{dir} => {
+                self.path.clear();
                 if self.look_towards(dir) {
                     self.behaviour = Chaser::NoIntel;
                 }
             }
+            Chaser::TakeCover{pos: target} |
+            Chaser::Retreat{pos: target} |
+            Chaser::Heal{pos: target} => {
+                self.path_to(target, palette, grid);
+                let walking = self.walk(speed, palette, grid);
+
+                let pos = self.pl.obj.pos;
+                let threat = self.threat.filter(|&threat| grid.ray_cast(palette, pos, threat - pos, true).full());
+                match (threat, walking) {
+                    (Some(threat), _) => {
+                        self.look_towards(threat - pos);
+                    }
+                    (None, Some(dir)) => {
+                        self.look_towards(dir);
+                    }
+                    (None, None) => if let Some(threat) = self.threat {
+                        // Be ready for the player to come around the corner
+                        self.look_towards(threat - pos);
+                    }
+                }
+
+                match self.behaviour {
+                    // Reloaded, back to the fight
+                    Chaser::TakeCover{..} if !self.reloading => self.behaviour = match self.threat {
+                        Some(threat) => Chaser::LastKnown{pos: threat, vel: threat - pos},
+                        None => Chaser::NoIntel,
+                    },
+                    Chaser::Retreat{..} if self.pl.health.hp >= self.tactics.retreat_health => self.behaviour = Chaser::NoIntel,
+                    // The pickup wasn't there
+                    Chaser::Heal{..} if walking.is_none() => self.behaviour = Chaser::NoIntel,
+                    _ => (),
+                }
+            }
+        }
+    }
+    /// Index of the closest sounding noise it can hear, ignoring its own
+    fn closest_heard<'a, I: IntoIterator<Item = &'a Noise>>(&self, noises: I, palette: &Palette, grid: &Grid) -> Option<usize> {
+        let pos = self.pl.obj.pos;
+        noises.into_iter()
+            .enumerate()
+            .filter(|(_, noise)| (noise.pos - pos).norm() > 32.)
+            .filter_map(|(i, noise)| noise.heard_at(pos, palette, grid).map(|d| (d, i)))
+            .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap())
+            .map(|(_, i)| i)
+    }
+    /// Goes to where something happened and looks in the direction it came from
+    #[inline]
+    pub fn investigate(&mut self, target: Point2) {
+        if self.behaviour.tactical() {
+            return
+        }
+        self.behaviour = Chaser::LastKnown {
+            pos: target,
+            vel: target - self.pl.obj.pos,
+        };
+    }
+    /// Goes to investigate the closest sounding of the noises it can hear
+    pub fn hear(&mut self, noises: &[Noise], palette: &Palette, grid: &Grid) {
+        if let Some(i) = self.closest_heard(noises, palette, grid) {
+            if self.awareness == Awareness::Unaware {
+                self.awareness = Awareness::Suspicious;
+            }
+            self.suspicion = self.suspicion.max(HEARD_SUSPICION);
+            self.investigate(noises[i].pos);
+        }
+    }
+    /// Answers the closest call for help it can hear by going to the position called out
+    pub fn hear_calls(&mut self, calls: &[(Noise, Point2)], palette: &Palette, grid: &Grid) {
+        if let Some(i) = self.closest_heard(calls.iter().map(|(call, _)| call), palette, grid) {
+            self.alert();
+            self.threat = Some(calls[i].1);
+            self.investigate(calls[i].1);
+        }
+    }
+    /// Whether an attacker at the point can take it down by surprise,
+    /// which is when it isn't looking for anyone and is facing away
+    pub fn can_be_taken_down(&self, from: Point2) -> bool {
+        let unaware = match self.behaviour {
+            Chaser::NoIntel => self.awareness != Awareness::Alerted,
+            _ => false,
+        };
+        let to_attacker = from - self.pl.obj.pos;
+        unaware && to_attacker.norm_squared() > 0. && angle_to_vec(self.pl.obj.rot).dot(&to_attacker.normalize()) <= FACING_AWAY_COS
+    }
+    /// Direction to shoot in to hit a target moving with the velocity
+    fn lead(&self, target: Point2, vel: Vector2) -> Vector2 {
+        let speed = self.pl.wep.as_ref().map(|wep| wep.weapon.projectile_speed).unwrap_or(BULLET_SPEED);
+        let time = (target - self.pl.obj.pos).norm() / speed;
+        target + vel * time - self.pl.obj.pos
+    }
+    /// The angle to shoot the player at, if it has been in sight long enough to react and the enemy is facing it
+    pub fn aim<R: Rng>(&self, player_pos: Point2, player_vel: Vector2, difficulty: Difficulty, rng: &mut R) -> Option<f32> {
+        if self.sight_time < self.archetype.reaction_time * difficulty.reaction_time() {
+            return None
+        }
+        let lead = self.lead(player_pos, player_vel);
+        if angle_to_vec(self.pl.obj.rot).angle(&lead) > FIRING_ANGLE {
+            return None
+        }
+        let inaccuracy = self.archetype.inaccuracy;
+        let mut error = self.aim_error;
+        if inaccuracy > 0. {
+            error += rng.gen_range(-inaccuracy, inaccuracy);
         }
-        Ok(())
+        Some(angle_from_vec(lead) + error / difficulty.accuracy())
     }
-    pub fn can_see(&self, p: Point2, palette: &Palette, grid: &Grid) -> bool {
+    /// Whether the point is in its field of view without walls or smoke in the way
+    pub fn can_see(&self, p: Point2, difficulty: Difficulty, areas: &[Area], palette: &Palette, grid: &Grid) -> bool {
         let dist = p-self.pl.obj.pos;
         let dir = angle_to_vec(self.pl.obj.rot);
 
-        dir.angle(&dist) <= VISIBILITY && grid.ray_cast(palette, self.pl.obj.pos, dist, true).full()
+        dist.norm() <= self.archetype.vision_range * difficulty.vision_range()
+            && dir.angle(&dist) <= self.archetype.vision_angle
+            && grid.ray_cast(palette, self.pl.obj.pos, dist, true).full()
+            && !areas.iter().any(|area| area.blocks_sight(self.pl.obj.pos, dist))
     }
 }
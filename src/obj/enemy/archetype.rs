@@ -0,0 +1,128 @@
+use lazy_static::lazy_static;
+
+use crate::obj::{health::Health, weapon::{Weapon, WEAPONS}};
+
+use std::fs::File;
+use std::io::Read;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use super::{Tactics, TACTICS, DEFAULT_TACTICS};
+
+lazy_static!{
+    pub static ref ARCHETYPES: HashMap<String, Archetype> = {
+        let mut file = File::open("resources/enemies/archetypes.toml").expect("archetypes.toml file");
+        let mut file_contents = String::new();
+        file.read_to_string(&mut file_contents).expect("Reading to succeed");
+
+        let templates: HashMap<String, ArchetypeTemplate> = toml::from_str(&file_contents).expect("well-defined archetypes");
+        let archetypes: HashMap<_, _> = templates.into_iter().map(|(k, v)| {
+            let archetype = v.build(&k);
+            (k, archetype)
+        }).collect();
+        assert!(archetypes.contains_key(DEFAULT_ARCHETYPE), "archetypes.toml needs a {:?} entry", DEFAULT_ARCHETYPE);
+        archetypes
+    };
+}
+
+/// Id of the archetype enemies get when nothing else is specified
+pub const DEFAULT_ARCHETYPE: &str = "grunt";
+
+/// A kind of enemy, from `resources/enemies/archetypes.toml`
+#[derive(Debug, Clone)]
+pub struct Archetype {
+    /// The key in `archetypes.toml`
+    pub id: Box<str>,
+    pub sprite: Box<str>,
+    /// Health it's placed with in the editor
+    pub health: Health,
+    /// Speed when chasing, patrols are walked slower
+    pub speed: f32,
+    /// Radians per second
+    pub turn_speed: f32,
+    /// Angle in radians from the middle to the edge of the vision cone
+    pub vision_angle: f32,
+    /// How far away it can see
+    pub vision_range: f32,
+    /// Most radians a shot will be off by
+    pub inaccuracy: f32,
+    /// Most radians its aim is off by when it first sees the player
+    pub aim_error: f32,
+    /// Seconds it takes for the aim to go from `aim_error` to spot on
+    pub aim_time: f32,
+    /// Seconds the player has to be in sight before it starts shooting
+    pub reaction_time: f32,
+    /// Tactics it's placed with in the editor
+    pub tactics: &'static Tactics,
+    /// Weapon it's placed with in the editor
+    pub weapon: Option<&'static Weapon>,
+}
+
+impl Archetype {
+    #[inline]
+    pub fn default_archetype() -> &'static Archetype {
+        &ARCHETYPES[DEFAULT_ARCHETYPE]
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ArchetypeTemplate {
+    sprite: Box<str>,
+    hp: f32,
+    #[serde(default)]
+    armour: f32,
+    speed: f32,
+    turn_speed: f32,
+    vision_angle: f32,
+    vision_range: f32,
+    inaccuracy: f32,
+    aim_error: f32,
+    aim_time: f32,
+    reaction_time: f32,
+    tactics: Option<String>,
+    weapon: Option<String>,
+}
+
+const DEG2RAD: f32 = PI / 180.;
+
+impl ArchetypeTemplate {
+    fn build(self, id: &str) -> Archetype {
+        let ArchetypeTemplate {
+            sprite,
+            hp,
+            armour,
+            speed,
+            turn_speed,
+            vision_angle,
+            vision_range,
+            inaccuracy,
+            aim_error,
+            aim_time,
+            reaction_time,
+            tactics,
+            weapon,
+        } = self;
+
+        let tactics = tactics.as_deref().unwrap_or(DEFAULT_TACTICS);
+
+        Archetype {
+            id: id.into(),
+            sprite,
+            health: Health {
+                hp,
+                armour,
+                max_hp: hp,
+            },
+            speed,
+            turn_speed,
+            vision_angle: vision_angle * DEG2RAD,
+            vision_range,
+            inaccuracy: inaccuracy * DEG2RAD,
+            aim_error: aim_error * DEG2RAD,
+            aim_time,
+            reaction_time,
+            tactics: TACTICS.get(tactics).unwrap_or_else(|| panic!("unknown tactics {:?} for {}", tactics, id)),
+            weapon: weapon.map(|w| WEAPONS.get(&w).unwrap_or_else(|| panic!("unknown weapon {:?} for {}", w, id))),
+        }
+    }
+}
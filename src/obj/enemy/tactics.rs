@@ -0,0 +1,73 @@
+use lazy_static::lazy_static;
+
+use std::fs::File;
+use std::io::Read;
+use std::collections::HashMap;
+
+lazy_static!{
+    pub static ref TACTICS: HashMap<String, Tactics> = {
+        let mut file = File::open("resources/enemies/tactics.toml").expect("tactics.toml file");
+        let mut file_contents = String::new();
+        file.read_to_string(&mut file_contents).expect("Reading to succeed");
+
+        let templates: HashMap<String, TacticsTemplate> = toml::from_str(&file_contents).expect("well-defined tactics");
+        let tactics: HashMap<_, _> = templates.into_iter().map(|(k, v)| {
+            let tactics = v.build(&k);
+            (k, tactics)
+        }).collect();
+        assert!(tactics.contains_key(DEFAULT_TACTICS), "tactics.toml needs a {:?} entry", DEFAULT_TACTICS);
+        tactics
+    };
+}
+
+/// Id of the tactics enemies get when nothing else is specified
+pub const DEFAULT_TACTICS: &str = "default";
+
+/// How an enemy fights, from `resources/enemies/tactics.toml`
+#[derive(Debug, Clone)]
+pub struct Tactics {
+    /// The key in `tactics.toml`
+    pub id: Box<str>,
+    /// Whether to hide from the player while reloading
+    pub cover_to_reload: bool,
+    /// Health below which the enemy retreats out of sight, 0 to never retreat
+    pub retreat_health: f32,
+    /// Health below which the enemy goes for health pickups
+    pub heal_health: f32,
+    /// How far away cover, places to retreat to and pickups are looked for
+    pub search_range: f32,
+}
+
+impl Tactics {
+    #[inline]
+    pub fn default_tactics() -> &'static Tactics {
+        &TACTICS[DEFAULT_TACTICS]
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TacticsTemplate {
+    cover_to_reload: bool,
+    retreat_health: f32,
+    heal_health: f32,
+    search_range: f32,
+}
+
+impl TacticsTemplate {
+    fn build(self, id: &str) -> Tactics {
+        let TacticsTemplate {
+            cover_to_reload,
+            retreat_health,
+            heal_health,
+            search_range,
+        } = self;
+
+        Tactics {
+            id: id.into(),
+            cover_to_reload,
+            retreat_health,
+            heal_health,
+            search_range,
+        }
+    }
+}
@@ -1,21 +1,155 @@
-use ggez::{Context, GameResult, graphics::WHITE};
+use ggez::{
+    Context, GameResult,
+    graphics::{self, WHITE, Color, DrawMode, Mesh},
+};
+use lazy_static::lazy_static;
 
 use crate::{
-    util::{angle_to_vec, Vector2},
+    util::{angle_to_vec, Point2, Vector2},
     game::{
         DELTA,
+        sim::Event,
         world::{Grid, Palette},
     },
-    io::{
-        snd::MediaPlayer,
-        tex::{Assets, },
-    },
+    io::tex::{Assets, },
 };
-use super::{Object, player::Player, enemy::Enemy, health::Health};
+use super::{Object, BODY_RADIUS, player::{Player, Effect, EffectKind}, enemy::Enemy};
+
+use std::fs::File;
+use std::io::Read;
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_2;
+
+lazy_static!{
+    pub static ref GRENADES: HashMap<String, GrenadeType> = {
+        let mut file = File::open("resources/weapons/grenades.toml").expect("grenades.toml file");
+        let mut file_contents = String::new();
+        file.read_to_string(&mut file_contents).expect("Reading to succeed");
+
+        let templates: HashMap<String, GrenadeTemplate> = toml::from_str(&file_contents).expect("well-defined grenades");
+        templates.into_iter().map(|(k, v)| {
+            let grenade_type = v.build(&k);
+            (k, grenade_type)
+        }).collect()
+    };
+}
+
+/// What a grenade does when its fuse runs out
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Blast {
+    /// Damages and pushes away everything in the radius that isn't behind a wall
+    Explosive {
+        /// Damage right where it goes off
+        damage: f32,
+        /// Exponent of how the damage falls off to nothing at the radius
+        ///
+        /// 0 doesn't fall off at all, 1 falls off linearly and higher falls off faster.
+        falloff: f32,
+        penetration: f32,
+        /// Pixels things right where it goes off are pushed, falling off like the damage
+        knockback: f32,
+    },
+    /// Leaves a cloud that can't be seen through
+    Smoke {
+        duration: f32,
+    },
+    /// Stuns everything in the radius facing the blast for `duration` seconds
+    Flashbang {
+        duration: f32,
+    },
+    /// Leaves a fire that sets everything in it burning with `burning` damage per second
+    Incendiary {
+        duration: f32,
+        burning: f32,
+        /// Seconds things keep burning after leaving the fire
+        burn_time: f32,
+    },
+}
+
+/// A kind of grenade, from `resources/weapons/grenades.toml`
+#[derive(Debug, Clone)]
+pub struct GrenadeType {
+    /// The key in `grenades.toml`
+    pub id: Box<str>,
+    pub name: Box<str>,
+    pub sprite: Box<str>,
+    /// Seconds from being thrown to going off
+    pub fuse: f32,
+    pub radius: f32,
+    pub sound: Box<str>,
+    /// How far enemies hear it go off
+    pub loudness: f32,
+    pub blast: Blast,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GrenadeTemplate {
+    name: Box<str>,
+    sprite: Box<str>,
+    fuse: f32,
+    radius: f32,
+    sound: Box<str>,
+    loudness: f32,
+    blast: Blast,
+}
+
+impl GrenadeTemplate {
+    pub(crate) fn build(self, id: &str) -> GrenadeType {
+        let GrenadeTemplate {
+            name,
+            sprite,
+            fuse,
+            radius,
+            sound,
+            loudness,
+            blast,
+        } = self;
+
+        GrenadeType {
+            id: id.into(),
+            name,
+            sprite,
+            fuse,
+            radius,
+            sound,
+            loudness,
+            blast,
+        }
+    }
+}
+
+impl GrenadeType {
+    /// How hard an explosive blast from `from` hits a body at `pos`, by how close it is and how much of it is in cover
+    pub fn strength(&self, palette: &Palette, grid: &Grid, from: Point2, pos: Point2) -> f32 {
+        match self.blast {
+            Blast::Explosive{falloff, ..} => {
+                let closeness = (1. - (pos - from).norm() / self.radius).max(0.);
+                exposure(palette, grid, from, self.radius, pos) * closeness.powf(falloff)
+            }
+            _ => 0.,
+        }
+    }
+    /// Damage its blast from `from` does to a prop at `pos`, ignoring armour
+    pub fn prop_damage(&self, palette: &Palette, grid: &Grid, from: Point2, pos: Point2) -> f32 {
+        match self.blast {
+            Blast::Explosive{damage, ..} => damage * self.strength(palette, grid, from, pos),
+            _ => 0.,
+        }
+    }
+}
 
-#[derive(Debug, Default, Clone, Copy)]
+/// The grenades the player carries
+#[derive(Debug, Default, Clone)]
 pub struct Utilities {
-    pub grenades: u8,
+    /// Grenades of each type in the order they were first picked up
+    pub grenades: Vec<(&'static GrenadeType, u8)>,
+    /// Index in `grenades` of the type that gets thrown
+    pub selected: usize,
+    /// Seconds the grenade about to be thrown has been held for
+    ///
+    /// Holding it makes the throw longer but the fuse is burning.
+    pub cook_time: Option<f32>,
 }
 
 #[derive(Debug, Clone)]
@@ -23,55 +157,41 @@ pub struct Grenade {
     pub obj: Object,
     pub vel: Vector2,
     pub fuse: f32,
+    pub grenade_type: &'static GrenadeType,
 }
 
 const DEC: f32 = 1.4;
+/// Rays cast from a blast across a body to find how much of it is in cover
+const COVER_RAYS: usize = 5;
+/// Most walls a grenade can bounce off in one step
+const MAX_BOUNCES: usize = 4;
+/// Speed of a grenade thrown without charging
+const MIN_THROW: f32 = 300.;
+/// Speed of a fully charged throw
+const MAX_THROW: f32 = 800.;
+/// Seconds to hold the throw for to charge it fully
+const CHARGE_TIME: f32 = 1.;
 
 impl Grenade {
-    #[inline]
-    pub fn apply_damage(&self, health: &mut Health, high: bool) {
-        health.weapon_damage(if high { 105.} else {55.}, 85.);
-    }
     #[inline]
     pub fn draw(&self, ctx: &mut Context, a: &Assets) -> GameResult<()> {
-        let img = a.get_img(ctx, "weapons/pineapple");
+        let img = a.get_img(ctx, &self.grenade_type.sprite);
         self.obj.draw(ctx, &*img, WHITE)
     }
-    pub fn update(&mut self, palette: &Palette, grid: &Grid, player: &mut Player, enemies: &mut [Enemy]) -> Option<Explosion> {
+    /// Moves the grenade and sets it off when the fuse runs out, the damage done to the player is scaled by `player_damage`
+    pub fn update(&mut self, palette: &Palette, grid: &Grid, player: &mut Player, player_damage: f32, enemies: &mut [Enemy]) -> Option<Detonation> {
         let start = self.obj.pos;
-        let d_vel = -DEC * self.vel * DELTA;
-        let d_pos = 0.5 * DELTA * d_vel + self.vel * DELTA;
-        self.vel += d_vel;
+        let d_pos = self.decelerate();
         if self.fuse > DELTA {
             self.fuse -= DELTA;
         } else {
             self.fuse = 0.;
-
-            let player_hit;
-            let mut enemy_hits = Vec::new();
-
-            let d_player = player.obj.pos-start;
-            if d_player.norm() < 144. && grid.ray_cast(palette, start, d_player, true).full() {
-                self.apply_damage(&mut player.health, d_player.norm() <= 64.);
-                player_hit = true;
-            } else {
-                player_hit = false;
-            }
-
-            for (i, enem) in enemies.iter_mut().enumerate().rev() {
-                let d_enemy = enem.pl.obj.pos - start;
-                if d_enemy.norm() < 144. && grid.ray_cast(palette, start, d_enemy, true).full() {
-                    self.apply_damage(&mut enem.pl.health, d_enemy.norm() <= 64.);
-                    enemy_hits.push(i);
-                }
-            }
-
-            return Some(Explosion{player_hit, enemy_hits});
+            return Some(self.detonate(palette, grid, player, player_damage, enemies));
         }
 
         let closest_p = Grid::closest_point_of_line_to_circle(start, d_pos, player.obj.pos);
         let r_player = player.obj.pos - closest_p;
-        if r_player.norm() <= 16. {
+        if r_player.norm() <= BODY_RADIUS {
             self.vel -= 2.*self.vel.dot(&r_player)/r_player.norm_squared() * r_player;
             let clip = (start + d_pos) - closest_p;
 
@@ -81,54 +201,336 @@ impl Grenade {
         for enem in enemies.iter_mut() {
             let closest_e = Grid::closest_point_of_line_to_circle(start, d_pos, enem.pl.obj.pos);
             let r_enemy = enem.pl.obj.pos - closest_e;
-            if r_enemy.norm() <= 16. {
+            if r_enemy.norm() <= BODY_RADIUS {
                 self.vel -= 2.*self.vel.dot(&r_enemy)/r_enemy.norm_squared() * r_enemy;
-                let clip = (start + d_pos) - closest_p;
+                let clip = (start + d_pos) - closest_e;
 
                 self.obj.pos = closest_e + clip - 2. * clip.dot(&r_enemy)/r_enemy.norm_squared()*r_enemy;
                 return None;
             }
         }
-        let cast = grid.ray_cast(palette, start, d_pos, true);
-        self.obj.pos = cast.into_point();
-        if let Some(to_wall) = cast.half_vec() {
-            let clip = cast.clip();
-            self.obj.pos += clip -  2. * clip.dot(&to_wall)/to_wall.norm_squared() * to_wall;
-            self.vel -= 2. * self.vel.dot(&to_wall)/to_wall.norm_squared() * to_wall;
-        }
+        self.move_on_grid(d_pos, palette, grid);
         None
     }
+    /// Slows the grenade down and returns how far it moves this step
+    fn decelerate(&mut self) -> Vector2 {
+        let d_vel = -DEC * self.vel * DELTA;
+        let d_pos = 0.5 * DELTA * d_vel + self.vel * DELTA;
+        self.vel += d_vel;
+        d_pos
+    }
+    /// Moves the grenade, bouncing off walls with the restitution of their material
+    fn move_on_grid(&mut self, mut d_pos: Vector2, palette: &Palette, grid: &Grid) {
+        // It can hit more than one wall in a step when it's thrown into a corner
+        for _ in 0..MAX_BOUNCES {
+            if d_pos.norm_squared() < std::f32::EPSILON {
+                return
+            }
+            let cast = grid.ray_cast(palette, self.obj.pos, d_pos, true);
+            self.obj.pos = cast.into_point();
+            let to_wall = match cast.half_vec() {
+                Some(to_wall) => to_wall,
+                None => return,
+            };
+            let restitution = grid.restitution(palette, self.obj.pos + 0.5 * to_wall);
+            let reflect = |v: Vector2| v - 2. * v.dot(&to_wall)/to_wall.norm_squared() * to_wall;
+
+            self.vel = restitution * reflect(self.vel);
+            d_pos = restitution * reflect(cast.clip());
+            // Off the edge of the tile so the next cast doesn't start in the wall
+            self.obj.pos -= 0.01 * to_wall;
+        }
+    }
+    /// Where the grenade goes until its fuse runs out if it doesn't bounce off anyone
+    pub fn trajectory(mut self, palette: &Palette, grid: &Grid) -> Vec<Point2> {
+        let mut points = vec![self.obj.pos];
+        while self.fuse > DELTA {
+            self.fuse -= DELTA;
+            let d_pos = self.decelerate();
+            self.move_on_grid(d_pos, palette, grid);
+            points.push(self.obj.pos);
+        }
+        points
+    }
+}
+
+impl Grenade {
+    fn detonate(&self, palette: &Palette, grid: &Grid, player: &mut Player, player_damage: f32, enemies: &mut [Enemy]) -> Detonation {
+        let start = self.obj.pos;
+        let radius = self.grenade_type.radius;
+        let in_blast = |pos: Point2| exposure(palette, grid, start, radius, pos) > 0.;
+
+        match self.grenade_type.blast {
+            Blast::Explosive{damage, penetration, knockback, ..} => {
+                let hit = |pl: &mut Player, scale: f32| {
+                    let strength = self.grenade_type.strength(palette, grid, start, pl.obj.pos);
+                    if strength > 0. {
+                        pl.health.weapon_damage(scale * damage * strength, penetration);
+                        knock_back(&mut pl.obj, start, knockback * strength, palette, grid);
+                    }
+                    strength > 0.
+                };
+
+                let player_hit = hit(player, player_damage);
+                let mut enemy_hits = Vec::new();
+                for (i, enem) in enemies.iter_mut().enumerate().rev() {
+                    let scale = enem.pl.effects.damage_factor();
+                    if hit(&mut enem.pl, scale) {
+                        enemy_hits.push(i);
+                    }
+                }
+                Detonation::Explosion(Explosion{player_hit, enemy_hits})
+            }
+            Blast::Flashbang{duration} => {
+                let stun = Effect {
+                    kind: EffectKind::Stunned,
+                    strength: 1.,
+                    time: duration,
+                };
+                // Only those looking towards the blast are blinded
+                let facing = |obj: &Object| angle_to_vec(obj.rot).angle(&(start - obj.pos)) <= FRAC_PI_2;
+
+                let player_hit = in_blast(player.obj.pos) && facing(&player.obj);
+                if player_hit {
+                    player.effects.add(stun);
+                }
+                let mut enemy_hits = Vec::new();
+                for (i, enem) in enemies.iter_mut().enumerate().rev() {
+                    if in_blast(enem.pl.obj.pos) && facing(&enem.pl.obj) {
+                        enem.pl.effects.add(stun);
+                        enemy_hits.push(i);
+                    }
+                }
+                Detonation::Flash(Explosion{player_hit, enemy_hits})
+            }
+            Blast::Smoke{duration} => Detonation::Area(Area {
+                pos: start,
+                radius,
+                time: duration,
+                kind: AreaKind::Smoke,
+            }),
+            Blast::Incendiary{duration, burning, burn_time} => Detonation::Area(Area {
+                pos: start,
+                radius,
+                time: duration,
+                kind: AreaKind::Fire{burning, burn_time},
+            }),
+        }
+    }
+}
+
+/// Fraction of a body at `pos` that a blast with the radius reaches, 0 if it's out of range or completely in cover
+///
+/// Rays are cast to points spread across the body, so a body sticking out from behind a wall is partly hit.
+pub fn exposure(palette: &Palette, grid: &Grid, from: Point2, radius: f32, pos: Point2) -> f32 {
+    let dist = pos - from;
+    if dist.norm() >= radius {
+        return 0.
+    }
+    if dist.norm_squared() == 0. {
+        return 1.
+    }
+    let across = Vector2::new(-dist.y, dist.x).normalize() * BODY_RADIUS;
+    let reached = (0..COVER_RAYS)
+        .map(|i| 2. * i as f32 / (COVER_RAYS - 1) as f32 - 1.)
+        .filter(|&t| grid.ray_cast(palette, from, dist + t * across, true).full())
+        .count();
+    reached as f32 / COVER_RAYS as f32
+}
+
+/// Pushes the object `dist` pixels away from a point, stopping at walls
+fn knock_back(obj: &mut Object, from: Point2, dist: f32, palette: &Palette, grid: &Grid) {
+    /// Pixels moved at a time so it can't skip past a wall
+    const STEP: f32 = 8.;
+
+    let dir = obj.pos - from;
+    if dir.norm_squared() == 0. {
+        return
+    }
+    let dir = dir.normalize();
+    let mut left = dist;
+    while left > 0. {
+        let step = left.min(STEP);
+        obj.move_on_grid(dir, step / DELTA, palette, grid);
+        left -= step;
+    }
 }
 
 impl Utilities {
-    pub fn throw_grenade(&mut self, ctx: &mut Context, mplayer: &mut MediaPlayer) -> GameResult<Option<GrenadeMaker>> {
-        if self.grenades > 0 {
-            self.grenades -= 1;
+    /// Total number of grenades
+    pub fn count(&self) -> u8 {
+        self.grenades.iter().map(|&(_, n)| n).sum()
+    }
+    /// The type that gets thrown and how many of it there are
+    pub fn selected(&self) -> Option<(&'static GrenadeType, u8)> {
+        self.grenades.get(self.selected).cloned()
+    }
+    pub fn add(&mut self, grenade_type: &'static GrenadeType, n: u8) {
+        if let Some(entry) = self.grenades.iter_mut().find(|(t, _)| t.id == grenade_type.id) {
+            entry.1 = entry.1.saturating_add(n);
+        } else {
+            self.grenades.push((grenade_type, n));
+        }
+        if self.selected().map(|(_, n)| n == 0).unwrap_or(true) {
+            self.cycle();
+        }
+    }
+    /// Selects the next type there are any grenades of
+    pub fn cycle(&mut self) {
+        let n = self.grenades.len();
+        for i in 1..=n {
+            let next = (self.selected + i) % n;
+            if self.grenades[next].1 > 0 {
+                self.selected = next;
+                return
+            }
+        }
+    }
+    /// The grenade that would be thrown now
+    pub fn aim(&self) -> Option<GrenadeMaker> {
+        let (grenade_type, n) = self.selected()?;
+        if n == 0 {
+            return None
+        }
+        let cook_time = self.cook_time.unwrap_or(0.);
+        let charge = (cook_time / CHARGE_TIME).min(1.);
+        Some(GrenadeMaker {
+            speed: MIN_THROW + (MAX_THROW - MIN_THROW) * charge,
+            fuse: (grenade_type.fuse - cook_time).max(0.),
+            grenade_type,
+        })
+    }
+    /// Starts holding a grenade to charge the throw
+    pub fn cook(&mut self) {
+        if self.cook_time.is_none() && self.aim().is_some() {
+            self.cook_time = Some(0.);
+        }
+    }
+    /// Counts up the time the grenade has been held, returns whether the fuse has burnt out
+    pub fn update(&mut self) -> bool {
+        let fuse = match self.selected() {
+            Some((grenade_type, _)) => grenade_type.fuse,
+            None => return false,
+        };
+        if let Some(cook_time) = &mut self.cook_time {
+            *cook_time += DELTA;
+            *cook_time >= fuse
+        } else {
+            false
+        }
+    }
+    pub fn throw_grenade(&mut self, events: &mut Vec<Event>) -> Option<GrenadeMaker> {
+        let gm = self.aim();
+        self.cook_time = None;
+        if let Some(gm) = gm {
+            self.grenades[self.selected].1 -= 1;
+            if self.grenades[self.selected].1 == 0 {
+                self.cycle();
+            }
 
-            mplayer.play(ctx, "throw")?;
-            Ok(Some(GrenadeMaker(620.)))
+            events.push(Event::Sound("throw"));
+            Some(gm)
         } else {
-            mplayer.play(ctx, "cock")?;
-            Ok(None)
+            events.push(Event::Sound("cock"));
+            None
         }
     }
 }
 
-pub struct GrenadeMaker(f32);
+pub struct GrenadeMaker {
+    speed: f32,
+    fuse: f32,
+    grenade_type: &'static GrenadeType,
+}
 impl GrenadeMaker {
     pub fn make(self, mut obj: Object) -> Grenade {
-        let vel = angle_to_vec(obj.rot) * self.0;
+        let vel = angle_to_vec(obj.rot) * self.speed;
         obj.rot = 0.;
         Grenade {
-            fuse: 1.5,
+            fuse: self.fuse,
             vel,
             obj,
+            grenade_type: self.grenade_type,
         }
     }
 }
 
+/// What happened when a grenade went off
+#[derive(Debug, Clone)]
+pub enum Detonation {
+    Explosion(Explosion),
+    /// A flashbang went off, stunning the ones hit
+    Flash(Explosion),
+    /// It left an area behind
+    Area(Area),
+}
+
 #[derive(Debug, Clone)]
 pub struct Explosion{
     pub player_hit: bool,
     pub enemy_hits: Vec<usize>,
 }
+
+#[derive(Debug, Clone, Copy)]
+pub enum AreaKind {
+    /// Blocks sight
+    Smoke,
+    /// Sets the ones in it burning
+    Fire {
+        burning: f32,
+        burn_time: f32,
+    },
+}
+
+/// A smoke cloud or fire left by a grenade
+#[derive(Debug, Clone)]
+pub struct Area {
+    pub pos: Point2,
+    pub radius: f32,
+    /// Seconds left
+    pub time: f32,
+    pub kind: AreaKind,
+}
+
+impl Area {
+    #[inline]
+    pub fn contains(&self, p: Point2) -> bool {
+        (p - self.pos).norm() <= self.radius
+    }
+    /// Whether smoke is in the way of a line
+    pub fn blocks_sight(&self, from: Point2, dist: Vector2) -> bool {
+        match self.kind {
+            AreaKind::Smoke => Grid::dist_line_circle(from, dist, self.pos) <= self.radius,
+            AreaKind::Fire{..} => false,
+        }
+    }
+    /// Counts the area down and sets the ones in a fire burning
+    pub fn update(&mut self, player: &mut Player, enemies: &mut [Enemy]) {
+        if let AreaKind::Fire{burning, burn_time} = self.kind {
+            let burn = Effect {
+                kind: EffectKind::Burning,
+                strength: burning,
+                time: burn_time,
+            };
+            if self.contains(player.obj.pos) {
+                player.effects.add(burn);
+            }
+            for enem in enemies.iter_mut() {
+                if self.contains(enem.pl.obj.pos) {
+                    enem.pl.effects.add(burn);
+                }
+            }
+        }
+        self.time -= DELTA;
+    }
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        // Fades out in the last second
+        let fade = self.time.min(1.);
+        let color = match self.kind {
+            AreaKind::Smoke => Color::new(0.7, 0.7, 0.7, 0.9 * fade),
+            AreaKind::Fire{..} => Color::new(1., 0.45, 0.1, 0.45 * fade),
+        };
+        let mesh = Mesh::new_circle(ctx, DrawMode::fill(), self.pos, self.radius, 1., color)?;
+        graphics::draw(ctx, &mesh, graphics::DrawParam::default())
+    }
+}
@@ -4,6 +4,14 @@ use crate::ext::FloatExt;
 pub struct Health {
     pub hp: f32,
     pub armour: f32,
+    /// The health that healing stops at, raised by some pickups
+    #[serde(skip, default = "default_max_hp")]
+    pub max_hp: f32,
+}
+
+#[inline]
+fn default_max_hp() -> f32 {
+    100.
 }
 
 impl Health {
@@ -19,6 +27,19 @@ impl Health {
             self.armour = 0.;
         }
     }
+    /// Heals by the amount but not above the maximum health, returns whether it wasn't already at it
+    pub fn heal(&mut self, hp: f32) -> bool {
+        if self.hp >= self.max_hp {
+            return false
+        }
+        self.hp = (self.hp + hp).min(self.max_hp);
+        true
+    }
+    /// Raises the maximum health, and the health as much
+    pub fn raise_max_hp(&mut self, hp: f32) {
+        self.max_hp += hp;
+        self.hp += hp;
+    }
     #[inline]
     pub fn is_dead(self) -> bool {
         self.hp <= 0.
@@ -30,6 +51,7 @@ impl Default for Health {
         Self {
             hp: 100.,
             armour: 0.,
+            max_hp: default_max_hp(),
         }
     }
 }
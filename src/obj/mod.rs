@@ -16,6 +16,9 @@ pub mod grenade;
 use crate::game::world::{Grid, Palette};
 use crate::game::DELTA;
 
+/// Radius of the bodies of the player and enemies
+pub const BODY_RADIUS: f32 = 16.;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// A simple object that can be drawn to the screen
 pub struct Object {
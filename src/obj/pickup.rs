@@ -6,10 +6,31 @@ use ggez::{
     GameResult, Context,
     graphics::self,
 };
+use lazy_static::lazy_static;
 
-use std::fmt::{self, Debug};
+use std::fs::File;
+use std::io::Read;
+use std::collections::HashMap;
 
-use super::health::Health;
+use super::{
+    health::Health,
+    player::{Player, Effect, EffectTemplate},
+    grenade::{GrenadeType, GRENADES},
+};
+
+lazy_static!{
+    pub static ref PICKUPS: HashMap<String, PickupType> = {
+        let mut file = File::open("resources/pickups/pickups.toml").expect("pickups.toml file");
+        let mut file_contents = String::new();
+        file.read_to_string(&mut file_contents).expect("Reading to succeed");
+
+        let templates: HashMap<String, PickupTemplate> = toml::from_str(&file_contents).expect("well-defined pickups");
+        templates.into_iter().map(|(k, v)| {
+            let pickup = v.build(&k);
+            (k, pickup)
+        }).collect()
+    };
+}
 
 #[derive(Debug, Clone)]
 pub struct Pickup {
@@ -19,16 +40,17 @@ pub struct Pickup {
 
 impl Pickup {
     #[inline]
-    pub fn new(pos: Point2, i: u8) -> Self {
+    pub fn new(pos: Point2, pickup_type: &'static PickupType) -> Self {
         Self {
             pos,
-            pickup_type: &PICKUPS[i as usize]
+            pickup_type,
         }
     }
+    /// Uses the pickup with its effect scaled by `effectiveness`, returns whether it did anything
     #[inline]
     #[must_use]
-    pub fn apply(&self, health: &mut Health) -> bool {
-        (self.pickup_type.ability)(health)
+    pub fn apply(&self, player: &mut Player, effectiveness: f32) -> bool {
+        self.pickup_type.apply(player, effectiveness)
     }
     #[inline]
     pub fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult<()> {
@@ -36,13 +58,84 @@ impl Pickup {
     }
 }
 
-#[derive(Copy, Clone)]
+/// A kind of pickup, from `resources/pickups/pickups.toml`
+#[derive(Debug, Clone)]
 pub struct PickupType {
-    pub spr: &'static str,
-    ability: fn(&mut Health) -> bool,
+    /// The key in `pickups.toml`
+    pub id: Box<str>,
+    pub spr: Box<str>,
+    /// Health given, but not above the maximum
+    pub heal: f32,
+    /// Health given, even above the maximum
+    pub overheal: f32,
+    /// Armour given, but not above 100
+    pub armour: f32,
+    /// Armour given, even above 100
+    pub extra_armour: f32,
+    /// How much the maximum health goes up, the health goes up as much
+    pub max_health: f32,
+    /// Magazines of spare ammo given for every weapon carried
+    pub magazines: u16,
+    /// Status effects given, their times are scaled by the effectiveness
+    pub effects: Vec<Effect>,
+    /// Grenades given of a type
+    pub grenades: Option<(&'static GrenadeType, u8)>,
 }
 
 impl PickupType {
+    /// Whether using it would give someone with the health any
+    #[inline]
+    pub fn heals(&self, health: &Health) -> bool {
+        (self.heal > 0. && health.hp < health.max_hp) || self.overheal > 0. || self.max_health > 0.
+    }
+    /// Uses the pickup with its effect scaled by `effectiveness`, returns whether it did anything
+    #[must_use]
+    pub fn apply(&self, player: &mut Player, effectiveness: f32) -> bool {
+        let health = &mut player.health;
+        let mut used = false;
+
+        if self.heal > 0. && health.heal(self.heal * effectiveness) {
+            used = true;
+        }
+        if self.armour > 0. && health.armour < 100. {
+            health.armour = (health.armour + self.armour * effectiveness).min(100.);
+            used = true;
+        }
+        if self.overheal > 0. {
+            health.hp += self.overheal * effectiveness;
+            used = true;
+        }
+        if self.extra_armour > 0. {
+            health.armour += self.extra_armour * effectiveness;
+            used = true;
+        }
+        if self.max_health > 0. {
+            health.raise_max_hp(self.max_health * effectiveness);
+            used = true;
+        }
+        if self.magazines > 0 {
+            let weapons: Vec<_> = player.weapons()
+                .filter_map(|wep| wep.weapon.ammo_type().map(|ammo_type| (ammo_type, wep.weapon.clip_size.get())))
+                .collect();
+            used |= !weapons.is_empty();
+            for (ammo_type, clip_size) in weapons {
+                let ammo = f32::from(self.magazines * clip_size) * effectiveness;
+                player.inventory.add_ammo(ammo_type, ammo.round() as u16);
+            }
+        }
+        if let Some((grenade_type, n)) = self.grenades {
+            player.utilities.add(grenade_type, n);
+            used = true;
+        }
+        for &effect in &self.effects {
+            player.effects.add(Effect {
+                time: effect.time * effectiveness,
+                .. effect
+            });
+            used = true;
+        }
+        used
+    }
     #[inline]
     pub fn draw(&self, pos: Point2, ctx: &mut Context, assets: &Assets) -> GameResult<()> {
         let drawparams = graphics::DrawParam {
@@ -50,58 +143,66 @@ impl PickupType {
             offset: Point2::new(0.5, 0.5).into(),
             .. Default::default()
         };
-        let img = assets.get_img(ctx, self.spr);
+        let img = assets.get_img(ctx, &self.spr);
         graphics::draw(ctx, &*img, drawparams)
     }
 }
 
-impl Debug for PickupType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("PickupType")
-            .field("spr", &self.spr)
-            .finish()
-    }
+#[derive(Serialize, Deserialize)]
+pub struct PickupTemplate {
+    sprite: Box<str>,
+    #[serde(default)]
+    heal: f32,
+    #[serde(default)]
+    overheal: f32,
+    #[serde(default)]
+    armour: f32,
+    #[serde(default)]
+    extra_armour: f32,
+    #[serde(default)]
+    max_health: f32,
+    #[serde(default)]
+    magazines: u16,
+    #[serde(default)]
+    effects: Vec<EffectTemplate>,
+    grenades: Option<GrenadesTemplate>,
 }
 
-pub const PICKUPS: [PickupType; 4] = [
-    PickupType {
-        spr: "pickups/health_pack",
-        ability: health_pack
-    },
-    PickupType {
-        spr: "pickups/armour",
-        ability: armour
-    },
-    PickupType {
-        spr: "pickups/adrenaline",
-        ability: adrenaline,
-    },
-    PickupType {
-        spr: "pickups/super_armour",
-        ability: super_armour
-    },
-];
-fn health_pack(health: &mut Health) -> bool {
-    if health.hp >= 100. {
-        false
-    } else {
-        health.hp = 100.;
-        true
-    }
+#[derive(Serialize, Deserialize)]
+pub struct GrenadesTemplate {
+    #[serde(rename = "type")]
+    grenade_type: String,
+    count: u8,
 }
-fn armour(health: &mut Health) -> bool {
-    if health.armour >= 100. {
-        false
-    } else {
-        health.armour = 100.;
-        true
+
+impl PickupTemplate {
+    fn build(self, id: &str) -> PickupType {
+        let PickupTemplate {
+            sprite,
+            heal,
+            overheal,
+            armour,
+            extra_armour,
+            max_health,
+            magazines,
+            effects,
+            grenades,
+        } = self;
+
+        PickupType {
+            id: id.into(),
+            spr: sprite,
+            heal,
+            overheal,
+            armour,
+            extra_armour,
+            max_health,
+            magazines,
+            effects: effects.into_iter().map(EffectTemplate::build).collect(),
+            grenades: grenades.map(|GrenadesTemplate{grenade_type, count}| {
+                let grenade_type = GRENADES.get(&grenade_type).unwrap_or_else(|| panic!("unknown grenade type {:?} for {}", grenade_type, id));
+                (grenade_type, count)
+            }),
+        }
     }
 }
-fn adrenaline(health: &mut Health) -> bool {
-    health.hp += 100.;
-    true
-}
-fn super_armour(health: &mut Health) -> bool {
-    health.armour += 100.;
-    true
-}
\ No newline at end of file
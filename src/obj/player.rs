@@ -2,13 +2,16 @@ use ggez::{Context, GameResult, graphics::{self, WHITE, Color}};
 
 use crate::{
     util::{Point2, angle_to_vec},
-    io::{
-        snd::MediaPlayer,
-        tex::{Assets, },
-    },
+    io::tex::{Assets, },
+    game::sim::Event,
 };
 
-use super::{Object, health::Health, weapon::WeaponInstance, grenade::Utilities};
+use super::{Object, health::Health, weapon::{Weapon, WeaponInstance, WeaponDrop, fists}, grenade::Utilities};
+
+mod inventory;
+pub use self::inventory::*;
+mod effects;
+pub use self::effects::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
@@ -19,6 +22,13 @@ pub struct Player {
     pub health: Health,
     #[serde(skip)]
     pub utilities: Utilities,
+    #[serde(skip)]
+    pub inventory: Inventory,
+    #[serde(skip)]
+    pub effects: Effects,
+    /// Seconds until it can swing its fists again
+    #[serde(skip)]
+    pub fists_time: f32,
 }
 
 impl Player {
@@ -29,6 +39,9 @@ impl Player {
             wep: None,
             health: Health::default(),
             utilities: Utilities::default(),
+            inventory: Inventory::default(),
+            effects: Effects::default(),
+            fists_time: 0.,
         }
     }
     #[inline]
@@ -42,11 +55,134 @@ impl Player {
             .. self
         }
     }
-    #[inline]
-    pub fn with_weapon(self, wep: Option<WeaponInstance<'static>>) -> Self {
-        Self {
-            wep,
-            .. self
+    /// Gives it the inventory with the weapon of the selected slot in its hands
+    pub fn with_inventory(mut self, mut inventory: Inventory) -> Self {
+        self.wep = match inventory.hands {
+            Hands::Weapon(slot) => inventory.holstered[slot.index()].take(),
+            Hands::Grenades => None,
+        };
+        self.inventory = inventory;
+        self
+    }
+    /// Its inventory with the weapon in its hands put back in its slot
+    pub fn loadout(&self) -> Inventory {
+        let mut inventory = self.inventory.clone();
+        if let (Hands::Weapon(slot), Some(wep)) = (inventory.hands, self.wep) {
+            inventory.holstered[slot.index()] = Some(wep);
+        }
+        inventory
+    }
+    /// The melee weapon in its hands, or its fists if it has none
+    pub fn melee_weapon(&self) -> &'static Weapon {
+        match self.wep {
+            Some(wep) if wep.weapon.swing.is_some() => wep.weapon,
+            _ => fists(),
+        }
+    }
+    /// Swings its melee weapon (or its fists) if it's ready, returning what it swung
+    pub fn swing(&mut self, events: &mut Vec<Event>) -> Option<&'static Weapon> {
+        match &mut self.wep {
+            Some(wep) if wep.weapon.swing.is_some() => if wep.swing(events) {
+                Some(wep.weapon)
+            } else {
+                None
+            },
+            _ => if self.fists_time == 0. {
+                let fists = fists();
+                self.fists_time = fists.fire_rate;
+                events.push(Event::Sound(&fists.shot_snd));
+                Some(fists)
+            } else {
+                None
+            },
+        }
+    }
+    /// Whether there is anything to take out of the slot
+    pub fn has(&self, hands: Hands) -> bool {
+        match hands {
+            Hands::Grenades => self.utilities.count() > 0,
+            Hands::Weapon(slot) => if hands == self.inventory.hands {
+                self.wep.is_some()
+            } else {
+                self.inventory.holstered[slot.index()].is_some()
+            },
+        }
+    }
+    /// Puts away what's in its hands and takes out what's in the slot
+    ///
+    /// The weapon can't be fired until it has been drawn.
+    pub fn switch_to(&mut self, hands: Hands) {
+        if hands == self.inventory.hands || !self.has(hands) {
+            return
+        }
+        let holster_time = self.wep.map(|wep| wep.weapon.holster_time).unwrap_or(0.);
+        if let (Hands::Weapon(slot), Some(wep)) = (self.inventory.hands, self.wep.take()) {
+            self.inventory.holstered[slot.index()] = Some(wep);
+        }
+        self.inventory.hands = hands;
+        if let Hands::Weapon(slot) = hands {
+            self.wep = self.inventory.holstered[slot.index()].take();
+            if let Some(wep) = &mut self.wep {
+                wep.loading_time = holster_time + wep.weapon.draw_time;
+            }
+        }
+    }
+    /// Switches to the next (or previous) slot that has anything in it
+    pub fn cycle(&mut self, forward: bool) {
+        let n = Hands::ALL.len();
+        let cur = Hands::ALL.iter().position(|&hands| hands == self.inventory.hands).unwrap_or(0);
+        for i in 1..n {
+            let hands = Hands::ALL[if forward { (cur + i) % n } else { (cur + n - i) % n }];
+            if self.has(hands) {
+                return self.switch_to(hands)
+            }
+        }
+    }
+    /// Puts the weapon in its slot and its ammo with the spare ammo, returning the weapon that was in the slot
+    pub fn pick_up(&mut self, drop: WeaponDrop<'static>) -> Option<WeaponDrop<'static>> {
+        let pos = drop.pos;
+        let slot = drop.weapon.slot;
+        if let Some(ammo_type) = drop.weapon.ammo_type() {
+            self.inventory.add_ammo(ammo_type, drop.ammo);
+        }
+        let wep = WeaponInstance::from_drop(WeaponDrop {
+            ammo: 0,
+            .. drop
+        });
+
+        let in_hands = match self.inventory.hands {
+            Hands::Weapon(held) => held == slot || self.wep.is_none(),
+            Hands::Grenades => false,
+        };
+        let old = if in_hands {
+            self.inventory.hands = Hands::Weapon(slot);
+            self.wep.replace(wep)
+        } else {
+            self.inventory.holstered[slot.index()].replace(wep)
+        };
+        old.map(|wep| wep.into_drop(pos))
+    }
+    /// The weapon in its hands and the ones put away
+    pub fn weapons(&self) -> impl Iterator<Item=&WeaponInstance<'static>> {
+        self.wep.iter().chain(self.inventory.holstered.iter().flatten())
+    }
+    /// Whether it carries a weapon using the ammo type
+    pub fn uses_ammo(&self, ammo_type: &str) -> bool {
+        self.weapons().any(|wep| wep.weapon.ammo_type() == Some(ammo_type))
+    }
+    /// Spare ammo for the weapon in its hands
+    pub fn spare_ammo(&self) -> u16 {
+        self.wep.and_then(|wep| wep.weapon.ammo_type()).map(|ammo_type| self.inventory.ammo_of(ammo_type)).unwrap_or(0)
+    }
+    /// Reloads the weapon in its hands from the spare ammo
+    pub fn reload(&mut self, events: &mut Vec<Event>) {
+        if let Some(wep) = &mut self.wep {
+            let ammo_type = match wep.weapon.ammo_type() {
+                Some(ammo_type) => ammo_type,
+                None => return,
+            };
+            let ammo = self.inventory.ammo.entry(ammo_type).or_insert(0);
+            wep.reload_from(ammo, events);
         }
     }
 
@@ -68,10 +204,9 @@ impl Player {
         let img = a.get_img(ctx, sprite);
         self.obj.draw(ctx, &*img, color)
     }
-    pub fn update(&mut self, ctx: &mut Context, mplayer: &mut MediaPlayer) -> GameResult<()> {
+    pub fn update(&mut self, events: &mut Vec<Event>) {
         if let Some(wep) = &mut self.wep {
-            wep.update(ctx, mplayer)?;
+            wep.update(events);
         }
-        Ok(())
     }
 }
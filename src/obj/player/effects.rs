@@ -0,0 +1,140 @@
+use crate::{
+    game::DELTA,
+    obj::health::Health,
+};
+
+/// A kind of status effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectKind {
+    /// Moves faster by the strength as a fraction
+    SpeedBoost,
+    /// Ignores the strength as a fraction of all damage
+    Resistance,
+    /// Loses the strength in health per second, ignoring armour
+    Bleeding,
+    /// Moves slower by the strength as a fraction
+    Slowed,
+    /// Loses the strength in health per second, ignoring armour
+    Burning,
+    /// Can't see or shoot, the strength is how blinded
+    Stunned,
+}
+
+impl EffectKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EffectKind::SpeedBoost => "speed boost",
+            EffectKind::Resistance => "resistance",
+            EffectKind::Bleeding => "bleeding",
+            EffectKind::Slowed => "slowed",
+            EffectKind::Burning => "burning",
+            EffectKind::Stunned => "stunned",
+        }
+    }
+    /// Whether getting it again adds to the strength instead of only keeping the strongest
+    ///
+    /// Either way the longest time is kept.
+    #[inline]
+    fn stacks(self) -> bool {
+        matches!(self, EffectKind::Bleeding)
+    }
+}
+
+/// An effect that lasts for some time
+#[derive(Debug, Clone, Copy)]
+pub struct Effect {
+    pub kind: EffectKind,
+    pub strength: f32,
+    /// Seconds left
+    pub time: f32,
+}
+
+/// An effect as written in the resource files, lasting `duration` seconds
+#[derive(Serialize, Deserialize)]
+pub struct EffectTemplate {
+    kind: EffectKind,
+    strength: f32,
+    duration: f32,
+}
+
+impl EffectTemplate {
+    pub fn build(self) -> Effect {
+        let EffectTemplate{kind, strength, duration} = self;
+        Effect {
+            kind,
+            strength,
+            time: duration,
+        }
+    }
+}
+
+/// The effects on a player or an enemy, at most one of each kind
+#[derive(Debug, Clone, Default)]
+pub struct Effects {
+    effects: Vec<Effect>,
+}
+
+impl Effects {
+    /// Adds the effect, combining it with one of the same kind by its stacking rule
+    pub fn add(&mut self, effect: Effect) {
+        if effect.time <= 0. {
+            return
+        }
+        if let Some(cur) = self.effects.iter_mut().find(|cur| cur.kind == effect.kind) {
+            cur.strength = if effect.kind.stacks() {
+                cur.strength + effect.strength
+            } else {
+                cur.strength.max(effect.strength)
+            };
+            cur.time = cur.time.max(effect.time);
+        } else {
+            self.effects.push(effect);
+        }
+    }
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item=&Effect> {
+        self.effects.iter()
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+    #[inline]
+    pub fn get(&self, kind: EffectKind) -> Option<&Effect> {
+        self.effects.iter().find(|effect| effect.kind == kind)
+    }
+    /// Strength of the effect of the kind, 0 without it
+    #[inline]
+    pub fn strength(&self, kind: EffectKind) -> f32 {
+        self.get(kind).map(|effect| effect.strength).unwrap_or(0.)
+    }
+    /// Factor on the movement speed
+    pub fn speed_factor(&self) -> f32 {
+        (1. + self.strength(EffectKind::SpeedBoost)) * (1. - self.strength(EffectKind::Slowed)).max(0.)
+    }
+    /// Factor on the damage taken
+    pub fn damage_factor(&self) -> f32 {
+        (1. - self.strength(EffectKind::Resistance)).max(0.)
+    }
+    #[inline]
+    pub fn stunned(&self) -> bool {
+        self.get(EffectKind::Stunned).is_some()
+    }
+    /// Deals the damage over time scaled by `damage` and counts the effects down, removing the ones that ran out
+    ///
+    /// Returns whether any damage was dealt.
+    pub fn update(&mut self, health: &mut Health, damage: f32) -> bool {
+        let dps = self.strength(EffectKind::Bleeding) + self.strength(EffectKind::Burning);
+        let hurt = dps > 0.;
+        if hurt {
+            health.weapon_damage(dps * damage * self.damage_factor() * DELTA, 1.);
+        }
+
+        for effect in &mut self.effects {
+            effect.time -= DELTA;
+        }
+        self.effects.retain(|effect| effect.time > 0.);
+        hurt
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use crate::obj::weapon::{Slot, WeaponInstance};
+
+/// What the player has in its hands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hands {
+    Weapon(Slot),
+    Grenades,
+}
+
+impl Hands {
+    /// In the order of the number keys
+    pub const ALL: [Hands; 4] = [
+        Hands::Weapon(Slot::Primary),
+        Hands::Weapon(Slot::Secondary),
+        Hands::Weapon(Slot::Melee),
+        Hands::Grenades,
+    ];
+}
+
+impl Default for Hands {
+    #[inline]
+    fn default() -> Self {
+        Hands::Weapon(Slot::Primary)
+    }
+}
+
+/// The weapons and ammo the player carries
+#[derive(Debug, Clone, Default)]
+pub struct Inventory {
+    /// Weapons that have been put away, indexed by `Slot::index`
+    ///
+    /// The weapon in the hands is `Player::wep` and is not in here.
+    pub holstered: [Option<WeaponInstance<'static>>; 3],
+    pub hands: Hands,
+    /// Spare ammo by `Weapon::ammo_type`
+    pub ammo: HashMap<&'static str, u16>,
+}
+
+impl Inventory {
+    #[inline]
+    pub fn ammo_of(&self, ammo_type: &str) -> u16 {
+        self.ammo.get(ammo_type).cloned().unwrap_or(0)
+    }
+    pub fn add_ammo(&mut self, ammo_type: &'static str, ammo: u16) {
+        let reserve = self.ammo.entry(ammo_type).or_insert(0);
+        *reserve = reserve.saturating_add(ammo);
+    }
+}
@@ -2,16 +2,13 @@ use std::num::NonZeroU16;
 use std::fmt::{self, Display};
 
 use crate::{
-    util::Point2,
-    game::DELTA,
-    io::{
-        snd::MediaPlayer,
-        tex::{PosText, Assets},
-    },
+    util::{Point2, angle_to_vec},
+    game::{DELTA, sim::Event, world::{Grid, Palette}},
+    io::tex::{PosText, Assets},
 };
-use ggez::{Context, GameResult};
+use ggez::GameResult;
 
-use super::{Object, bullet::Bullet};
+use super::{Object, bullet::{Bullet, BodyPart}, grenade::GrenadeType, player::{Player, Effect}};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum FireMode {
@@ -34,12 +31,85 @@ impl FireMode {
     }
 }
 
+/// The inventory slot a weapon is carried in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    Primary,
+    Secondary,
+    Melee,
+}
+
+impl Slot {
+    pub const ALL: [Slot; 3] = [Slot::Primary, Slot::Secondary, Slot::Melee];
+
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How much of a weapon's damage is done by hitting each part of a body
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct BodyMultipliers {
+    pub head: f32,
+    pub torso: f32,
+    pub limbs: f32,
+}
+
+impl Default for BodyMultipliers {
+    fn default() -> Self {
+        BodyMultipliers {
+            head: 1.,
+            torso: 1.,
+            limbs: 1.,
+        }
+    }
+}
+
+impl BodyMultipliers {
+    #[inline]
+    pub fn get(self, part: BodyPart) -> f32 {
+        match part {
+            BodyPart::Head => self.head,
+            BodyPart::Torso => self.torso,
+            BodyPart::Limbs => self.limbs,
+        }
+    }
+}
+
+/// How a melee weapon hits
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Swing {
+    /// Pixels from the middle of the body to the middle of what it can hit
+    pub reach: f32,
+    /// Angle of the arc in front of the body it hits in
+    pub arc: f32,
+}
+
+impl Swing {
+    /// Whether a body at the target is in the arc in front of the object and not behind a wall
+    pub fn reaches(self, obj: &Object, target: Point2, palette: &Palette, grid: &Grid) -> bool {
+        let dist = target - obj.pos;
+        if dist.norm() > self.reach {
+            return false
+        }
+        let in_arc = dist.norm_squared() == 0. || angle_to_vec(obj.rot).dot(&dist.normalize()) >= (self.arc / 2.).cos();
+        in_arc && grid.ray_cast(palette, obj.pos, dist, true).full()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Weapon {
     pub name: Box<str>,
+    pub slot: Slot,
+    /// Spare ammo is shared between weapons of the same calibre, melee weapons have none
+    pub calibre: Option<Box<str>>,
     pub clip_size: NonZeroU16,
     pub clips: NonZeroU16,
     pub damage: f32,
+    /// Multipliers on the damage by the part of the body hit
+    pub multipliers: BodyMultipliers,
     /// Fraction of armour damage rediverted to hp damage
     pub penetration: f32,
     /// Time between each shot
@@ -57,6 +127,25 @@ pub struct Weapon {
     pub spray_pattern: Box<[f32]>,
     pub spray_decay: f32,
     pub spray_repeat: usize,
+    /// How far away the shots can be heard
+    pub loudness: f32,
+    /// Time to take it out when switching to it
+    pub draw_time: f32,
+    /// Time to put it away when switching from it
+    pub holster_time: f32,
+    /// Status effects given to the ones hit with it
+    pub effects: Box<[Effect]>,
+    /// Projectiles fired with each shot
+    pub pellets: u8,
+    /// Angle the pellets of a shot are spread evenly across
+    pub spread: f32,
+    /// Pixels per second its projectiles fly
+    pub projectile_speed: f32,
+    pub projectile_sprite: Box<str>,
+    /// What its projectiles blow up like when they hit something
+    pub explosion: Option<GrenadeType>,
+    /// How it hits if it's a melee weapon, which doesn't fire anything or use ammo
+    pub swing: Option<Swing>,
 }
 
 mod consts;
@@ -75,6 +164,18 @@ impl Weapon {
             ammo: cur_clip*self.clips.get(),
         }
     }
+    /// Deals the damage to the one hit with it and gives it the weapon's effects
+    pub fn hurt(&self, pl: &mut Player, damage: f32) {
+        pl.health.weapon_damage(damage, self.penetration);
+        for &effect in &*self.effects {
+            pl.effects.add(effect);
+        }
+    }
+    /// What its spare ammo is counted as in the player's inventory, if it uses any
+    #[inline]
+    pub fn ammo_type(&self) -> Option<&str> {
+        self.calibre.as_deref()
+    }
     pub fn make_drop(&self, pos: Point2) -> WeaponDrop<'_> {
         let cur_clip = self.clip_size.get();
         WeaponDrop {
@@ -121,11 +222,12 @@ impl<'a> WeaponInstance<'a> {
     pub fn weapon_text(p: Point2, a: &Assets) -> PosText {
         a.text(p).and_text("BFG").and_text(" ").and_text("0").and_text("/").and_text("0").and_text(" (").and_text("0").and_text(" ").and_text("0").and_text("s)")
     }
-    pub fn update_text(&self, text: &mut PosText) -> GameResult<()> {
+    /// Updates a text made by `weapon_text`, showing `ammo` as the spare ammo
+    pub fn update_text(&self, text: &mut PosText, ammo: u16) -> GameResult<()> {
         text
             .update(0, &*self.weapon.name)?
             .update(2, format!("{}", self.cur_clip))?
-            .update(4, format!("{}", self.ammo))?
+            .update(4, format!("{}", ammo))?
             .update(6, format!("{:.3}", self.jerk))?
             .update(8, format!("{:.1}", self.jerk_decay))?;
         Ok(())
@@ -152,7 +254,10 @@ impl<'a> WeaponInstance<'a> {
             weapon,
         }
     }
-    pub fn update(&mut self, ctx: &mut Context, mplayer: &mut MediaPlayer) -> GameResult<()> {
+}
+
+impl WeaponInstance<'static> {
+    pub fn update(&mut self, events: &mut Vec<Event>) {
         if self.jerk_decay <= DELTA {
             self.jerk = 0.;
             self.jerk_decay = 0.;
@@ -164,32 +269,48 @@ impl<'a> WeaponInstance<'a> {
             self.loading_time = 0.;
         } else {
             self.loading_time -= DELTA;
-            if self.loading_time <= DELTA {
-                mplayer.play(ctx, &self.weapon.cock_snd)?;
+            // Melee weapons have nothing to cock
+            if self.loading_time <= DELTA && self.weapon.swing.is_none() {
+                events.push(Event::Sound(&self.weapon.cock_snd));
             }
         }
-        Ok(())
     }
-    pub fn reload(&mut self, ctx: &mut Context, mplayer: &mut MediaPlayer) -> GameResult<()> {
+    pub fn reload(&mut self, events: &mut Vec<Event>) {
+        let mut ammo = self.ammo;
+        self.reload_from(&mut ammo, events);
+        self.ammo = ammo;
+    }
+    /// Reloads with ammo taken from somewhere else than the weapon itself
+    pub fn reload_from(&mut self, ammo: &mut u16, events: &mut Vec<Event>) {
         let clip_size = self.weapon.clip_size.get();
-        if self.cur_clip == clip_size || self.ammo == 0 {
-            return Ok(())
+        if self.cur_clip == clip_size || *ammo == 0 {
+            return
         }
 
         self.loading_time = self.weapon.reload_time;
 
         let ammo_to_reload = self.weapon.clip_size.get() - self.cur_clip;
 
-        if self.ammo < ammo_to_reload {
-            self.cur_clip += self.ammo;
-            self.ammo = 0;
+        if *ammo < ammo_to_reload {
+            self.cur_clip += *ammo;
+            *ammo = 0;
         } else {
-            self.ammo -= ammo_to_reload;
+            *ammo -= ammo_to_reload;
             self.cur_clip = clip_size;
         }
-        mplayer.play(ctx, &self.weapon.reload_snd)
+        events.push(Event::Sound(&self.weapon.reload_snd));
+    }
+    /// Swings it if it's a melee weapon that's ready, returns whether it did
+    pub fn swing(&mut self, events: &mut Vec<Event>) -> bool {
+        if self.weapon.swing.is_some() && self.loading_time == 0. {
+            self.loading_time = self.weapon.fire_rate;
+            events.push(Event::Sound(&self.weapon.shot_snd));
+            true
+        } else {
+            false
+        }
     }
-    pub fn shoot(&mut self, ctx: &mut Context, mplayer: &mut MediaPlayer) -> GameResult<Option<BulletMaker<'a>>> {
+    pub fn shoot(&mut self, events: &mut Vec<Event>) -> Option<BulletMaker<'static>> {
         if self.cur_clip > 0 && self.loading_time == 0. {
             self.cur_clip -= 1;
             if self.cur_clip > 0 {
@@ -205,24 +326,32 @@ impl<'a> WeaponInstance<'a> {
                 self.spray_index -= self.weapon.spray_repeat;
             }
 
-            mplayer.play(ctx, &self.weapon.shot_snd)?;
-            Ok(Some(BulletMaker(self.weapon, jerk)))
+            events.push(Event::Sound(&self.weapon.shot_snd));
+            Some(BulletMaker(self.weapon, jerk))
         } else {
             if self.cur_clip == 0 {
-                mplayer.play(ctx, &self.weapon.click_snd)?;
+                events.push(Event::Sound(&self.weapon.click_snd));
             }
-            Ok(None)
+            None
         }
     }
 }
 
 pub struct BulletMaker<'a>(&'a Weapon, f32);
 impl<'a> BulletMaker<'a> {
-    pub fn make(self, mut obj: Object) -> Bullet<'a> {
-        obj.rot += self.1;
-        Bullet {
-            obj,
-            weapon: self.0
-        }
+    /// The projectiles of the shot, one for each pellet
+    pub fn make(self, obj: Object) -> impl Iterator<Item=Bullet<'a>> {
+        let BulletMaker(weapon, jerk) = self;
+        let pellets = weapon.pellets.max(1);
+        (0..pellets).map(move |i| {
+            let spread = if pellets > 1 {
+                weapon.spread * (f32::from(i) / f32::from(pellets - 1) - 0.5)
+            } else {
+                0.
+            };
+            let mut obj = obj.clone();
+            obj.rot += jerk + spread;
+            Bullet::new(obj, weapon)
+        })
     }
 }
@@ -1,4 +1,5 @@
-use super::{FireMode, Weapon};
+use super::{FireMode, Slot, Weapon, BodyMultipliers, Swing};
+use crate::obj::{bullet::SPEED, grenade::GrenadeTemplate, player::EffectTemplate};
 
 use lazy_static::lazy_static;
 
@@ -15,16 +16,29 @@ lazy_static!{
         file.read_to_string(&mut file_contents).expect("Reading to succeed");
 
         let templates: HashMap<String, WeaponTemplate> = toml::from_str(&file_contents).expect("well-defined weapons");
-        templates.into_iter().map(|(k, v)| (k, v.build())).collect()
+        templates.into_iter().map(|(k, v)| {
+            let weapon = v.build(&k);
+            (k, weapon)
+        }).collect()
     };
 }
 
+/// What anyone without a melee weapon in their hands hits with
+#[inline]
+pub fn fists() -> &'static Weapon {
+    &WEAPONS["fists"]
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct WeaponTemplate {
     name: Box<str>,
+    slot: Slot,
+    calibre: Option<Box<str>>,
     clip_size: NonZeroU16,
     clips: NonZeroU16,
     damage: f32,
+    #[serde(default)]
+    multipliers: BodyMultipliers,
     penetration: f32,
     fire_rate: f32,
     reload_time: f32,
@@ -38,17 +52,31 @@ pub struct WeaponTemplate {
     spray_pattern: Vec<f32>,
     spray_decay: f32,
     spray_repeat: usize,
+    loudness: f32,
+    draw_time: f32,
+    holster_time: f32,
+    #[serde(default)]
+    effects: Vec<EffectTemplate>,
+    pellets: Option<u8>,
+    spread: Option<f32>,
+    projectile_speed: Option<f32>,
+    projectile_sprite: Option<Box<str>>,
+    explosion: Option<GrenadeTemplate>,
+    swing: Option<Swing>,
 }
 
 const DEG2RAD: f32 = PI / 180.;
 
 impl WeaponTemplate {
-    fn build(self) -> Weapon {
+    fn build(self, id: &str) -> Weapon {
         let WeaponTemplate {
             name,
+            slot,
+            calibre,
             clip_size,
             clips,
             damage,
+            multipliers,
             penetration,
             fire_rate,
             reload_time,
@@ -62,13 +90,26 @@ impl WeaponTemplate {
             spray_pattern,
             spray_decay,
             spray_repeat,
+            loudness,
+            draw_time,
+            holster_time,
+            effects,
+            pellets,
+            spread,
+            projectile_speed,
+            projectile_sprite,
+            explosion,
+            swing,
         } = self;
 
         Weapon {
             name,
+            slot,
+            calibre,
             clip_size,
             clips,
             damage,
+            multipliers,
             penetration,
             fire_rate,
             reload_time,
@@ -83,6 +124,16 @@ impl WeaponTemplate {
             spray_pattern: spray_pattern.into_iter().map(|deg| deg * DEG2RAD).collect(),
             spray_decay,
             spray_repeat,
+            loudness,
+            draw_time,
+            holster_time,
+            effects: effects.into_iter().map(EffectTemplate::build).collect(),
+            pellets: pellets.unwrap_or(1),
+            spread: spread.unwrap_or(0.) * DEG2RAD,
+            projectile_speed: projectile_speed.unwrap_or(SPEED),
+            projectile_sprite: projectile_sprite.unwrap_or_else(|| "common/bullet".into()),
+            explosion: explosion.map(|explosion| explosion.build(id)),
+            swing: swing.map(|Swing{reach, arc}| Swing{reach, arc: arc * DEG2RAD}),
         }
     }
 }